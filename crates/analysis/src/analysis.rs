@@ -1,9 +1,20 @@
-use lsp_types::CompletionItem;
+use lsp_types::{CompletionItem, DocumentSymbol, Hover, Range, SymbolInformation};
 
 use crate::change::AnalysisChange;
+use crate::compiler_diagnostics;
 use crate::completion;
-use crate::db::{FileDiagnostic, FilePosition, RootDatabase};
-use utils::{io, MoveFilePath};
+use crate::db::{FileDiagnostic, FilePosition, FileRange, RootDatabase};
+use crate::dependencies;
+use crate::document_symbols;
+use crate::goto_definition;
+use crate::hover;
+use crate::infer::{self, Ty};
+use crate::items::Item;
+use crate::references;
+use crate::rename::{self, FileEdits};
+use crate::resolve;
+use crate::workspace_symbols;
+use utils::FileId;
 
 #[derive(Debug, Default)]
 pub struct AnalysisHost {
@@ -22,6 +33,11 @@ impl AnalysisHost {
     pub fn apply_change(&mut self, change: AnalysisChange) {
         self.db.apply_change(change);
     }
+
+    /// Continues the revisions of the replaced host, results of its checks are told apart by them.
+    pub fn continue_revisions(&mut self, previous: &AnalysisHost) {
+        self.db.continue_revisions(previous.db.revision());
+    }
 }
 
 #[derive(Debug)]
@@ -42,58 +58,68 @@ impl Analysis {
         completion::completions(self.db(), position)
     }
 
-    pub fn check_file_with_compiler(
-        &self,
-        fpath: MoveFilePath,
-        text: &str,
-    ) -> Option<FileDiagnostic> {
-        match self.check_file_with_compiler_inner(fpath, text) {
-            Ok(_) => None,
-            Err(mut ds) => Some(ds.remove(0)),
-        }
+    pub fn hover(&self, position: FilePosition) -> Option<Hover> {
+        hover::hover(self.db(), position)
+    }
+
+    /// Declaration of the identifier under the cursor.
+    pub fn resolve(&self, position: FilePosition) -> Option<Item> {
+        resolve::resolve(self.db(), &position)
+    }
+
+    /// Type of the innermost expression under the cursor.
+    pub fn expr_type(&self, position: FilePosition) -> Option<Ty> {
+        infer::expr_type_at(self.db(), &position)
+    }
+
+    pub fn goto_definition(&self, position: FilePosition) -> Option<FileRange> {
+        goto_definition::goto_definition(self.db(), position)
     }
 
-    #[inline]
-    fn check_file_with_compiler_inner(
+    pub fn find_references(
         &self,
-        current_fpath: MoveFilePath,
-        current_text: &str,
-    ) -> Result<(), Vec<FileDiagnostic>> {
-        let deps: Vec<(MoveFilePath, String)> = self
-            .read_stdlib_files()
-            .into_iter()
-            .chain(self.db.module_files().into_iter())
-            .filter(|(fpath, _)| *fpath != current_fpath)
-            .collect();
-
-        let current_file = (current_fpath, current_text.to_string());
-        self.db
-            .config
-            .dialect()
-            .check_with_compiler(current_file, deps, self.db.config.sender())
-            .map_err(|errors| {
-                errors
-                    .into_iter()
-                    .map(
-                        |err| match self.db.compiler_error_into_diagnostic(err.clone()) {
-                            Ok(d) => d,
-                            Err(error) => panic!(
-                                "While converting {:#?} into Diagnostic, error occurred: {:?}",
-                                err,
-                                error.to_string()
-                            ),
-                        },
-                    )
-                    .collect()
-            })
-    }
-
-    fn read_stdlib_files(&self) -> Vec<(MoveFilePath, String)> {
-        self.db
-            .config
-            .stdlib_folder
-            .as_ref()
-            .map(|folder| io::read_move_files(folder.as_path()))
-            .unwrap_or_else(Vec::new)
+        position: FilePosition,
+        include_declaration: bool,
+    ) -> Option<Vec<FileRange>> {
+        references::find_references(self.db(), position, include_declaration)
+    }
+
+    pub fn highlight_references(&self, position: FilePosition) -> Option<Vec<Range>> {
+        references::highlight_references(self.db(), position)
+    }
+
+    pub fn document_symbols(&self, fpath: FileId) -> Vec<DocumentSymbol> {
+        document_symbols::document_symbols(self.db(), fpath)
+    }
+
+    pub fn workspace_symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        workspace_symbols::workspace_symbols(self.db(), query)
+    }
+
+    pub fn prepare_rename(&self, position: FilePosition) -> Option<Range> {
+        rename::prepare_rename(self.db(), position)
+    }
+
+    pub fn rename(&self, position: FilePosition, new_name: &str) -> Result<FileEdits, String> {
+        rename::rename(self.db(), position, new_name)
+    }
+
+    /// Tracked files, which need to be checked again after the `changed` files change.
+    pub fn dependent_files(&self, changed: &[FileId]) -> Vec<FileId> {
+        dependencies::dependent_files(self.db(), changed)
+    }
+
+    /// Syntax errors of the file from the tree-sitter parse, available before the compiler runs.
+    pub fn check_file_syntax(&self, fpath: FileId) -> FileDiagnostic {
+        self.db.syntax_diagnostics(fpath)
+    }
+
+    /// All compiler errors, grouped per file. Errors might point into the dependency files.
+    /// Result is reused until the file or its dependencies change, if `text` is the tracked one.
+    pub fn check_file_with_compiler(&self, fpath: FileId, text: &str) -> Vec<FileDiagnostic> {
+        if self.db.file_text(fpath) == Some(text) {
+            return self.db.compiler_diagnostics(fpath).to_vec();
+        }
+        compiler_diagnostics::compiler_diagnostics(&self.db, fpath, text)
     }
 }
@@ -1,11 +1,29 @@
+use lsp_types::Range;
+use tree_sitter::Point;
+
 use crate::config::Config;
-use utils::MoveFilePath;
+use crate::utils::location::File;
+use utils::FileId;
+
+pub use tree_sitter::InputEdit;
 
 #[derive(Debug)]
 pub enum RootChange {
-    AddFile(MoveFilePath, String),
-    ChangeFile(MoveFilePath, String),
-    RemoveFile(MoveFilePath),
+    AddFile(FileId, String),
+    ChangeFile(FileId, String),
+    EditFile(FileId, String, Vec<InputEdit>),
+    RemoveFile(FileId),
+}
+
+impl RootChange {
+    pub fn fpath(&self) -> FileId {
+        match *self {
+            RootChange::AddFile(fpath, _)
+            | RootChange::ChangeFile(fpath, _)
+            | RootChange::EditFile(fpath, _, _)
+            | RootChange::RemoveFile(fpath) => fpath,
+        }
+    }
 }
 
 #[derive(Default, Debug)]
@@ -19,17 +37,23 @@ impl AnalysisChange {
         AnalysisChange::default()
     }
 
-    pub fn add_file(&mut self, fname: MoveFilePath, text: String) {
+    pub fn add_file(&mut self, fname: FileId, text: String) {
         self.tracked_files_changed
             .push(RootChange::AddFile(fname, text));
     }
 
-    pub fn update_file(&mut self, fname: MoveFilePath, text: String) {
+    pub fn update_file(&mut self, fname: FileId, text: String) {
         self.tracked_files_changed
             .push(RootChange::ChangeFile(fname, text));
     }
 
-    pub fn remove_file(&mut self, fname: MoveFilePath) {
+    /// New `text` of the file, along with the `edits` which produced it from the previous one.
+    pub fn edit_file(&mut self, fname: FileId, text: String, edits: Vec<InputEdit>) {
+        self.tracked_files_changed
+            .push(RootChange::EditFile(fname, text, edits));
+    }
+
+    pub fn remove_file(&mut self, fname: FileId) {
         self.tracked_files_changed
             .push(RootChange::RemoveFile(fname))
     }
@@ -38,3 +62,110 @@ impl AnalysisChange {
         self.config_changed = Some(config);
     }
 }
+
+/// Replaces `range` of the `text` with `new_text`, the whole text gets replaced if there's no range.
+/// Returns the edit in the form tree-sitter needs for the incremental reparse.
+pub fn apply_text_change(
+    text: &mut String,
+    range: Option<Range>,
+    new_text: &str,
+) -> Option<InputEdit> {
+    let (start_byte, old_end_byte) = match range {
+        Some(range) => {
+            let file = File::new(text.as_str());
+            let start = file.byte_index(range.start)?;
+            let end = file.byte_index(range.end)?;
+            if start > end {
+                return None;
+            }
+            (start, end)
+        }
+        None => (0, text.len()),
+    };
+    let start_position = byte_point(text, start_byte);
+    let old_end_position = byte_point(text, old_end_byte);
+
+    text.replace_range(start_byte..old_end_byte, new_text);
+    let new_end_byte = start_byte + new_text.len();
+    let new_end_position = byte_point(text, new_end_byte);
+    Some(InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte,
+        start_position,
+        old_end_position,
+        new_end_position,
+    })
+}
+
+/// Tree-sitter point of the byte index: row and the byte offset inside of the row.
+fn byte_point(text: &str, byte_index: usize) -> Point {
+    let before = &text[..byte_index];
+    let row = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    Point::new(row, byte_index - line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::Position;
+
+    fn range(start: (u64, u64), end: (u64, u64)) -> Option<Range> {
+        Some(Range::new(
+            Position::new(start.0, start.1),
+            Position::new(end.0, end.1),
+        ))
+    }
+
+    #[test]
+    fn test_apply_ranged_text_changes() {
+        let mut text = "script {\n    fun main() {}\n}\n".to_string();
+
+        let edit = apply_text_change(&mut text, range((1, 8), (1, 12)), "start").unwrap();
+        assert_eq!(text, "script {\n    fun start() {}\n}\n");
+        assert_eq!(edit.start_byte, 17);
+        assert_eq!(edit.old_end_byte, 21);
+        assert_eq!(edit.new_end_byte, 22);
+        assert_eq!(edit.new_end_position, Point::new(1, 13));
+
+        let edit = apply_text_change(
+            &mut text,
+            range((1, 17), (1, 17)),
+            "\n        let a = 1;\n    ",
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "script {\n    fun start() {\n        let a = 1;\n    }\n}\n"
+        );
+        assert_eq!(edit.old_end_position, Point::new(1, 17));
+        assert_eq!(edit.new_end_position, Point::new(3, 4));
+
+        // columns past the end of the line are clamped
+        apply_text_change(&mut text, range((4, 0), (4, 100)), "} ").unwrap();
+        assert_eq!(
+            text,
+            "script {\n    fun start() {\n        let a = 1;\n    }\n} \n"
+        );
+    }
+
+    #[test]
+    fn test_change_columns_are_utf16_code_units() {
+        let mut text = "script {\n    // \u{1F600}ab\n}\n".to_string();
+        // the emoji takes two UTF-16 code units and four bytes
+        let edit = apply_text_change(&mut text, range((1, 10), (1, 11)), "c").unwrap();
+        assert_eq!(text, "script {\n    // \u{1F600}ac\n}\n");
+        assert_eq!(edit.start_byte, 21);
+        assert_eq!(edit.old_end_byte, 22);
+    }
+
+    #[test]
+    fn test_apply_full_text_change() {
+        let mut text = "script {}".to_string();
+        let edit = apply_text_change(&mut text, None, "module M {\n}").unwrap();
+        assert_eq!(text, "module M {\n}");
+        assert_eq!(edit.old_end_position, Point::new(0, 9));
+        assert_eq!(edit.new_end_position, Point::new(1, 1));
+    }
+}
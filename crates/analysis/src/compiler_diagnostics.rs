@@ -0,0 +1,42 @@
+use crate::db::{group_by_file, FileDiagnostic, RootDatabase};
+use utils::FileId;
+
+/// All compiler errors of the file, grouped per file. Errors might point into the dependency files.
+/// Dependencies are the stdlib and the tracked modules, their parses are reused between checks.
+pub fn compiler_diagnostics(
+    db: &RootDatabase,
+    current_fpath: FileId,
+    current_text: &str,
+) -> Vec<FileDiagnostic> {
+    let config = db.config();
+    let deps = db
+        .dependency_fpaths()
+        .iter()
+        .filter(|fpath| **fpath != current_fpath)
+        .map(|fpath| (*fpath, db.parsed_file(*fpath)))
+        .collect();
+
+    let current_file = (current_fpath, current_text.to_string());
+    let check_result = config.dialect().check_parsed_with_compiler(
+        current_file,
+        deps,
+        config.sender(),
+        &config.address_placeholders,
+    );
+    match check_result {
+        Ok(_) => vec![],
+        Err(errors) => group_by_file(errors.into_iter().filter_map(|err| {
+            match db.compiler_error_into_diagnostic(err.clone()) {
+                Ok(d) => Some(d),
+                Err(error) => {
+                    log::error!(
+                        "While converting {:#?} into Diagnostic, error occurred: {:?}",
+                        err,
+                        error.to_string()
+                    );
+                    None
+                }
+            }
+        })),
+    }
+}
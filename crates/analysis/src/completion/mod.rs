@@ -1,7 +1,12 @@
 use crate::completion::scope::{determine_scope, Scope};
 use crate::db::{FilePosition, RootDatabase};
-use lsp_types::CompletionItem;
+use crate::items::{Item, ItemKind};
+use crate::resolve::Resolver;
+use crate::utils::tree::{ancestors, node_text, position_to_point};
+use dialects::base::Dialect;
+use lsp_types::{CompletionItem, CompletionItemKind, Documentation, Position};
 use syntax::ast::SourceFile;
+use tree_sitter::Point;
 
 mod scope;
 
@@ -29,20 +34,13 @@ pub fn keywords(context: &CompletionContext) -> Vec<CompletionItem> {
         .collect()
 }
 
-pub fn builtins(context: &CompletionContext) -> Vec<CompletionItem> {
+pub fn builtins(context: &CompletionContext, dialect: &dyn Dialect) -> Vec<CompletionItem> {
     let items = match context.scope {
-        Scope::Function => vec![
-            "borrow_global",
-            "emit_event",
-            "borrow_global_mut",
-            "exists",
-            "move_from",
-            "move_to_sender",
-        ],
-        _ => vec![],
+        Scope::Function => dialect.builtins(),
+        _ => &[],
     };
     items
-        .into_iter()
+        .iter()
         .map(|label| CompletionItem::new_simple(label.to_string(), "builtin".to_string()))
         .collect()
 }
@@ -57,12 +55,73 @@ impl CompletionContext {
     }
 }
 
+/// Functions and structs of the module before the `::`, if the cursor follows `Module::`
+/// or `0x1::Module::`. Private functions are only visible from the module itself.
+pub fn module_members(db: &RootDatabase, position: &FilePosition) -> Option<Vec<CompletionItem>> {
+    let (line, column) = position.pos;
+    let source_file = db.any_source_file(position.fpath)?;
+    let source = source_file.source();
+    let cursor = position_to_point(source, Position::new(line as u64, column as u64));
+    let point = Point::new(cursor.row, cursor.column.checked_sub(1)?);
+    let node = source_file
+        .tree
+        .root_node()
+        .descendant_for_point_range(point, point)?;
+    let module_access = ancestors(node).find(|node| node.kind() == "module_access")?;
+    let module_ident = module_access.child_by_field_name("module")?;
+    if module_ident.end_position() >= cursor {
+        return None;
+    }
+
+    let index = db.item_index();
+    let resolver = Resolver::new(
+        &index,
+        position.fpath,
+        source,
+        &db.config().sender().normalized_original,
+    );
+    let module = resolver.resolve_module_ref(module_access, node_text(source, module_ident))?;
+    let is_current_module = matches!(
+        resolver.current_module(module_access),
+        Some(current) if current.is_at(&module.address, &module.name)
+    );
+    let members = module
+        .items
+        .iter()
+        .filter(|item| match item.kind {
+            ItemKind::Function | ItemKind::NativeFunction => item.is_public || is_current_module,
+            kind => kind.is_struct(),
+        })
+        .map(member_completion)
+        .collect();
+    Some(members)
+}
+
+fn member_completion(item: &Item) -> CompletionItem {
+    let kind = if item.kind.is_function() {
+        CompletionItemKind::Function
+    } else {
+        CompletionItemKind::Struct
+    };
+    CompletionItem {
+        kind: Some(kind),
+        documentation: item.docs.clone().map(Documentation::String),
+        ..CompletionItem::new_simple(item.name.clone(), item.signature.clone())
+    }
+}
+
 pub fn completions(db: &RootDatabase, position: FilePosition) -> Vec<CompletionItem> {
-    let source_file = db.source_file(position.fpath);
+    if let Some(members) = module_members(db, &position) {
+        return members;
+    }
+    let source_file = match db.any_source_file(position.fpath) {
+        Some(source_file) => source_file,
+        None => return vec![],
+    };
     let context = determine_completion_context(source_file, position.pos);
 
     let mut completions = vec![];
     completions.extend(keywords(&context));
-    completions.extend(builtins(&context));
+    completions.extend(builtins(&context, db.config().dialect().as_ref()));
     completions
 }
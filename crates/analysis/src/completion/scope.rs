@@ -1,5 +1,7 @@
+use lsp_types::Position;
 use syntax::ast::{Definition, SourceFile};
-use tree_sitter::Point;
+
+use crate::utils::tree::position_to_point;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Scope {
@@ -16,7 +18,10 @@ pub fn determine_scope(source_file: SourceFile, pos: (usize, usize)) -> Scope {
     // first position of node belongs to previous node for autocompletion
     // let column = pos.1.saturating_sub(1);
     let column = pos.1;
-    let pos = Point::new(pos.0, column);
+    let pos = position_to_point(
+        source_file.source(),
+        Position::new(pos.0 as u64, column as u64),
+    );
 
     let definition = match source_file.definition() {
         Some(definition) => definition,
@@ -1,9 +1,12 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use core::fmt;
 
 use dialects::base::Dialect;
-use dialects::shared::ProvidedAccountAddress;
+use dialects::impls::SpecDialect;
+use dialects::shared::{AddressPlaceholders, ProvidedAccountAddress};
 use dialects::DialectName;
 use serde::export::fmt::Debug;
 use serde::export::Formatter;
@@ -13,33 +16,50 @@ use utils::io;
 #[derive(Clone)]
 pub struct Config {
     pub dialect_name: DialectName,
+    /// Human-readable prefix of bech32 addresses, overrides the one of the dialect.
+    pub bech32_hrp: Option<String>,
     pub stdlib_folder: Option<PathBuf>,
     pub modules_folders: Vec<PathBuf>,
     pub sender_address: ProvidedAccountAddress,
+    /// Addresses for `{{name}}` placeholders, other than `{{sender}}`.
+    pub address_placeholders: AddressPlaceholders,
+    /// Size of the pool computing diagnostics and answering requests, number of CPUs if not set.
+    pub worker_threads: Option<usize>,
+    /// Delay of the diagnostics after the last edit of the opened file.
+    pub diagnostics_debounce: Duration,
 }
 
+const DEFAULT_DIAGNOSTICS_DEBOUNCE_MS: u64 = 200;
+
 impl Debug for Config {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Config")
             .field("dialect", &self.dialect_name)
+            .field("bech32_hrp", &self.bech32_hrp)
             .field("stdlib_folder", &self.stdlib_folder)
             .field("module_folders", &self.modules_folders)
             .field("sender_address", &self.sender_address)
+            .field("address_placeholders", &self.address_placeholders)
+            .field("worker_threads", &self.worker_threads)
+            .field("diagnostics_debounce", &self.diagnostics_debounce)
             .finish()
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Config {
-            dialect_name: DialectName::Libra,
+        let mut config = Config {
+            dialect_name: DialectName::default(),
+            bech32_hrp: None,
             stdlib_folder: None,
             modules_folders: vec![],
-            sender_address: DialectName::Libra
-                .get_dialect()
-                .normalize_account_address("0x1")
-                .unwrap(),
-        }
+            sender_address: ProvidedAccountAddress::default(),
+            address_placeholders: AddressPlaceholders::default(),
+            worker_threads: None,
+            diagnostics_debounce: Duration::from_millis(DEFAULT_DIAGNOSTICS_DEBOUNCE_MS),
+        };
+        config.sender_address = config.default_sender();
+        config
     }
 }
 
@@ -63,7 +83,18 @@ impl Config {
     }
 
     pub fn dialect(&self) -> Box<dyn Dialect> {
-        self.dialect_name.get_dialect()
+        let dialect = SpecDialect::new(self.dialect_name.spec());
+        match &self.bech32_hrp {
+            Some(hrp) => Box::new(dialect.with_bech32_hrp(hrp)),
+            None => Box::new(dialect),
+        }
+    }
+
+    fn default_sender(&self) -> ProvidedAccountAddress {
+        let dialect = self.dialect();
+        dialect
+            .normalize_account_address(dialect.default_sender())
+            .unwrap()
     }
 
     pub fn sender(&self) -> &ProvidedAccountAddress {
@@ -74,6 +105,7 @@ impl Config {
         log::info!("Passed configuration = {:#}", value);
 
         set(value, "/dialect", &mut self.dialect_name);
+        self.bech32_hrp = get(value, "/bech32_hrp");
         self.stdlib_folder = match get::<PathBuf>(value, "/stdlib_folder") {
             None => {
                 log::error!("\"stdlib_folder\" not specified or invalid, standard library won't be loaded");
@@ -119,9 +151,36 @@ impl Config {
                 },
             )
             .unwrap_or_else(|| {
-                log::info!("Using default account address 0x1");
-                self.dialect().normalize_account_address("0x1").unwrap()
+                let address = self.default_sender();
+                log::info!("Using default account address {}", address.original);
+                address
             });
+        let dialect = self.dialect();
+        self.address_placeholders =
+            get::<BTreeMap<String, String>>(value, "/address_placeholders")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(name, address)| {
+                    if name == "sender" {
+                        log::error!(
+                            "\"sender\" placeholder is set with \"sender_address\", skipping it"
+                        );
+                        return None;
+                    }
+                    match dialect.normalize_account_address(&address) {
+                        Ok(provided_address) => Some((name, provided_address)),
+                        Err(error) => {
+                            log::error!("Invalid address of {:?} placeholder: {:?}", name, error);
+                            None
+                        }
+                    }
+                })
+                .collect();
+        self.worker_threads =
+            get::<usize>(value, "/worker_threads").filter(|threads| *threads > 0);
+        self.diagnostics_debounce = Duration::from_millis(
+            get(value, "/diagnostics_debounce_ms").unwrap_or(DEFAULT_DIAGNOSTICS_DEBOUNCE_MS),
+        );
 
         log::info!("Config updated to = {:#?}", self);
         self.log_available_module_files();
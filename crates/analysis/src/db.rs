@@ -1,109 +1,451 @@
 use anyhow::Result;
-use lsp_types::{Diagnostic, DiagnosticRelatedInformation, Location, Range, Url};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Range, Url,
+};
 
 use crate::change::{AnalysisChange, RootChange};
+use crate::compiler_diagnostics;
 use crate::config::Config;
+use crate::dependencies::{self, ModuleDependents, ModuleRef};
+use crate::items::{self, ItemIndex, ModuleDef};
+use crate::query::{Input, QueryTable, Revision};
+use crate::syntax_diagnostics;
 use crate::utils::location::File;
-use dialects::shared::errors::{CompilerError, CompilerErrorPart};
+use dialects::base::ParseResult;
+use dialects::shared::errors::{CompilerError, CompilerErrorPart, Severity};
 use serde::export::fmt::Debug;
 use serde::export::Formatter;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, Mutex};
 use syntax::ast::SourceFile;
-use utils::{FilesSourceText, MoveFilePath};
+use utils::{io, FileId, FilesSourceText};
 
+#[derive(Clone)]
 pub struct FileDiagnostic {
-    pub fpath: MoveFilePath,
-    pub diagnostic: Option<Diagnostic>,
+    pub fpath: FileId,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Debug for FileDiagnostic {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut debug_struct = f.debug_struct("FileDiagnostic");
-
-        debug_struct.field("fpath", &self.fpath.to_string());
-        if self.diagnostic.is_some() {
-            let Diagnostic { range, message, .. } = self.diagnostic.as_ref().unwrap();
-            debug_struct
-                .field(
-                    "range",
-                    &format!(
-                        "({}, {}) -> ({}, {})",
-                        range.start.line,
-                        range.start.character,
-                        range.end.line,
-                        range.end.character,
-                    ),
+        let diagnostics: Vec<String> = self
+            .diagnostics
+            .iter()
+            .map(|Diagnostic { range, message, .. }| {
+                format!(
+                    "({}, {}) -> ({}, {}): {}",
+                    range.start.line,
+                    range.start.character,
+                    range.end.line,
+                    range.end.character,
+                    message
                 )
-                .field("message", message);
-        }
-        debug_struct.finish()
+            })
+            .collect();
+        f.debug_struct("FileDiagnostic")
+            .field("fpath", &self.fpath.to_string())
+            .field("diagnostics", &diagnostics)
+            .finish()
     }
 }
 
 impl FileDiagnostic {
-    pub fn new(fpath: MoveFilePath, diagnostic: Diagnostic) -> FileDiagnostic {
+    pub fn new(fpath: FileId, diagnostic: Diagnostic) -> FileDiagnostic {
         FileDiagnostic {
             fpath,
-            diagnostic: Some(diagnostic),
+            diagnostics: vec![diagnostic],
         }
     }
 
-    pub fn new_empty(fpath: MoveFilePath) -> FileDiagnostic {
+    pub fn new_empty(fpath: FileId) -> FileDiagnostic {
         FileDiagnostic {
             fpath,
-            diagnostic: None,
+            diagnostics: vec![],
+        }
+    }
+}
+
+/// Merges diagnostics for the same file into a single entry, skipping duplicates.
+/// Files keep the order of their first appearance.
+pub fn group_by_file(
+    file_diagnostics: impl IntoIterator<Item = FileDiagnostic>,
+) -> Vec<FileDiagnostic> {
+    let mut grouped: Vec<FileDiagnostic> = vec![];
+    for file_diagnostic in file_diagnostics {
+        let FileDiagnostic { fpath, diagnostics } = file_diagnostic;
+        let index = match grouped.iter().position(|group| group.fpath == fpath) {
+            Some(index) => index,
+            None => {
+                grouped.push(FileDiagnostic::new_empty(fpath));
+                grouped.len() - 1
+            }
+        };
+        let group = &mut grouped[index].diagnostics;
+        for diagnostic in diagnostics {
+            if !group.contains(&diagnostic) {
+                group.push(diagnostic);
+            }
         }
     }
+    grouped
 }
 
 pub struct FilePosition {
-    pub fpath: MoveFilePath,
+    pub fpath: FileId,
+    /// Line and column of the LSP position, column is in UTF-16 code units.
     pub pos: (usize, usize),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRange {
+    pub fpath: FileId,
+    pub range: Range,
+}
+
+#[derive(Debug, Default)]
+struct QueryStorage {
+    library_source_files: QueryTable<FileId, Option<Arc<Mutex<SourceFile>>>>,
+    file_modules: QueryTable<FileId, Arc<Vec<ModuleDef>>>,
+    item_index: QueryTable<(), Arc<ItemIndex>>,
+    file_module_refs: QueryTable<FileId, Arc<Vec<ModuleRef>>>,
+    module_dependents: QueryTable<(), Arc<ModuleDependents>>,
+    dependency_fpaths: QueryTable<(), Arc<Vec<FileId>>>,
+    parsed_files: QueryTable<FileId, ParseResult>,
+    syntax_diagnostics: QueryTable<FileId, FileDiagnostic>,
+    compiler_diagnostics: QueryTable<FileId, Arc<Vec<FileDiagnostic>>>,
+}
+
+impl QueryStorage {
+    /// Drops the values computed for the tracked file, once it's removed.
+    fn evict_file(&self, fpath: FileId) {
+        self.file_modules.evict(&fpath);
+        self.file_module_refs.evict(&fpath);
+        self.parsed_files.evict(&fpath);
+        self.syntax_diagnostics.evict(&fpath);
+        self.compiler_diagnostics.evict(&fpath);
+    }
+}
+
+/// Inputs of the analysis, along with the memoized queries computed from them.
+/// Inputs are changed only with `apply_change`, queries record the inputs they read
+/// and get recomputed only after some of those inputs change.
+/// Clones share the inputs and the memoized values, so snapshots are cheap.
 #[derive(Debug, Default, Clone)]
 pub struct RootDatabase {
-    pub config: Config,
-    pub available_files: FilesSourceText,
+    config: Config,
+    available_files: Arc<FilesSourceText>,
+    /// Parsed `available_files`, kept to reparse them incrementally on edits.
+    /// tree-sitter trees are not `Sync`, so each one is behind the lock.
+    source_files: Arc<HashMap<FileId, Arc<Mutex<SourceFile>>>>,
+    /// Stdlib and modules folders files, read from the disk along with the config.
+    stdlib_files: Arc<FilesSourceText>,
+    modules_folders_files: Arc<FilesSourceText>,
+    revision: Revision,
+    /// Revisions the inputs were last changed at.
+    changed_at: Arc<HashMap<Input, Revision>>,
+    queries: Arc<QueryStorage>,
+    /// Inputs read by the queries being computed, innermost last.
+    active_queries: RefCell<Vec<Vec<Input>>>,
 }
 
 impl RootDatabase {
-    pub fn module_files(&self) -> FilesSourceText {
-        self.available_files
-            .clone()
+    pub fn new(config: Config, available_files: FilesSourceText) -> RootDatabase {
+        let source_files = available_files
+            .iter()
+            .map(|(fpath, text)| {
+                let source_file = SourceFile::new(text.clone());
+                (*fpath, Arc::new(Mutex::new(source_file)))
+            })
+            .collect();
+        let mut db = RootDatabase {
+            available_files: Arc::new(available_files),
+            source_files: Arc::new(source_files),
+            ..RootDatabase::default()
+        };
+        db.set_config(config);
+        db
+    }
+
+    pub fn config(&self) -> &Config {
+        self.report_inputs(&[Input::Config]);
+        &self.config
+    }
+
+    /// Text of the tracked file.
+    pub fn file_text(&self, fpath: FileId) -> Option<&str> {
+        self.report_inputs(&[Input::File(fpath)]);
+        self.available_files.get(&fpath).map(String::as_str)
+    }
+
+    pub fn tracked_files(&self) -> Vec<FileId> {
+        self.report_inputs(&[Input::FileSet]);
+        self.available_files.keys().copied().collect()
+    }
+
+    /// Stdlib files along with the tracked files inside of the modules folders,
+    /// files are checked by the compiler against them.
+    pub fn dependency_fpaths(&self) -> Arc<Vec<FileId>> {
+        self.queries.dependency_fpaths.get(self, (), |db, _| {
+            db.report_inputs(&[Input::Config]);
+            let mut fpaths: Vec<_> = db
+                .stdlib_files
+                .keys()
+                .copied()
+                .chain(
+                    db.tracked_files()
+                        .into_iter()
+                        .filter(|fpath| db.is_fpath_for_a_module(*fpath)),
+                )
+                .collect();
+            fpaths.sort_unstable();
+            fpaths.dedup();
+            Arc::new(fpaths)
+        })
+    }
+
+    /// Tracked files along with the stdlib and the modules folders files.
+    pub fn all_fpaths(&self) -> Vec<FileId> {
+        self.report_inputs(&[Input::Config]);
+        let mut fpaths: Vec<_> = self
+            .stdlib_files
+            .keys()
+            .chain(self.modules_folders_files.keys())
+            .copied()
+            .chain(self.tracked_files())
+            .collect();
+        fpaths.sort_unstable();
+        fpaths.dedup();
+        fpaths
+    }
+
+    /// Tracked files along with the stdlib and the modules folders files.
+    /// Tracked text is used for files present in both.
+    pub fn all_files(&self) -> FilesSourceText {
+        self.all_fpaths()
             .into_iter()
-            .filter(|(f, _)| self.is_fpath_for_a_module(f))
+            .filter_map(|fpath| Some((fpath, self.any_file_text(fpath)?.to_string())))
             .collect()
     }
 
-    pub fn source_file(&self, fpath: MoveFilePath) -> SourceFile {
-        self.available_files
-            .get(fpath)
-            .unwrap_or_else(|| panic!("No entry found for key {:?}", fpath));
-        let source_text = self.available_files[fpath].clone();
-        SourceFile::new(source_text)
+    /// Text of the tracked file, or of the stdlib and modules folders one.
+    pub fn any_file_text(&self, fpath: FileId) -> Option<&str> {
+        if let Some(text) = self.file_text(fpath) {
+            return Some(text);
+        }
+        self.report_inputs(&[Input::Config]);
+        self.modules_folders_files
+            .get(&fpath)
+            .or_else(|| self.stdlib_files.get(&fpath))
+            .map(String::as_str)
+    }
+
+    /// Parsed tracked file.
+    pub fn source_file(&self, fpath: FileId) -> Option<SourceFile> {
+        self.report_inputs(&[Input::File(fpath)]);
+        self.source_files
+            .get(&fpath)
+            .map(|source_file| source_file.lock().unwrap().clone())
+    }
+
+    /// Parsed tracked file, or the stdlib and modules folders one.
+    pub fn any_source_file(&self, fpath: FileId) -> Option<SourceFile> {
+        self.report_inputs(&[Input::FileSet]);
+        if self.available_files.contains_key(&fpath) {
+            return self.source_file(fpath);
+        }
+        let source_file = self
+            .queries
+            .library_source_files
+            .get(self, fpath, |db, fpath| {
+                let text = db.any_file_text(fpath)?;
+                Some(Arc::new(Mutex::new(SourceFile::new(text.to_string()))))
+            })?;
+        let source_file = source_file.lock().unwrap().clone();
+        Some(source_file)
+    }
+
+    pub fn is_stdlib_file(&self, fpath: FileId) -> bool {
+        self.report_inputs(&[Input::Config]);
+        self.stdlib_files.contains_key(&fpath)
+    }
+
+    /// Modules declared in the file.
+    pub fn file_modules(&self, fpath: FileId) -> Arc<Vec<ModuleDef>> {
+        self.queries.file_modules.get(self, fpath, |db, fpath| {
+            let source_file = match db.any_source_file(fpath) {
+                Some(source_file) => source_file,
+                None => return Arc::new(vec![]),
+            };
+            let sender = &db.config().sender().normalized_original;
+            Arc::new(items::file_modules(fpath, &source_file, sender))
+        })
+    }
+
+    /// Modules of all the files, to resolve names against.
+    pub fn item_index(&self) -> Arc<ItemIndex> {
+        self.queries
+            .item_index
+            .get(self, (), |db, _| Arc::new(ItemIndex::new(db)))
+    }
+
+    /// Modules the tracked file refers to.
+    pub fn file_module_refs(&self, fpath: FileId) -> Arc<Vec<ModuleRef>> {
+        self.queries.file_module_refs.get(self, fpath, |db, fpath| {
+            let module_refs = db
+                .source_file(fpath)
+                .map(|source_file| dependencies::module_refs(&source_file))
+                .unwrap_or_default();
+            Arc::new(module_refs)
+        })
+    }
+
+    /// Tracked files referring to each module, recomputed once per change of the tracked files.
+    pub fn module_dependents(&self) -> Arc<ModuleDependents> {
+        self.queries.module_dependents.get(self, (), |db, _| {
+            Arc::new(dependencies::module_dependents(db))
+        })
+    }
+
+    /// Dialect parse of the dependency file.
+    pub fn parsed_file(&self, fpath: FileId) -> ParseResult {
+        self.queries.parsed_files.get(self, fpath, |db, fpath| {
+            let text = db.any_file_text(fpath).unwrap_or_default().to_string();
+            let config = db.config();
+            config.dialect().parse_file(
+                (fpath, text),
+                config.sender(),
+                &config.address_placeholders,
+            )
+        })
+    }
+
+    pub fn syntax_diagnostics(&self, fpath: FileId) -> FileDiagnostic {
+        self.queries
+            .syntax_diagnostics
+            .get(self, fpath, |db, fpath| match db.source_file(fpath) {
+                Some(source_file) => syntax_diagnostics::syntax_diagnostics(fpath, &source_file),
+                None => FileDiagnostic::new_empty(fpath),
+            })
+    }
+
+    /// Compiler errors of the tracked file, grouped per file.
+    pub fn compiler_diagnostics(&self, fpath: FileId) -> Arc<Vec<FileDiagnostic>> {
+        self.queries
+            .compiler_diagnostics
+            .get(self, fpath, |db, fpath| {
+                let text = db.file_text(fpath).unwrap_or_default();
+                Arc::new(compiler_diagnostics::compiler_diagnostics(db, fpath, text))
+            })
+    }
+
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Moves the revisions past the ones of the `previous` database, replaced by this one.
+    pub fn continue_revisions(&mut self, previous: Revision) {
+        self.revision += previous;
+    }
+
+    pub(crate) fn changed_at(&self, input: Input) -> Revision {
+        self.changed_at.get(&input).copied().unwrap_or_default()
+    }
+
+    /// Records `inputs` as read by the query being computed.
+    pub(crate) fn report_inputs(&self, inputs: &[Input]) {
+        if let Some(query_inputs) = self.active_queries.borrow_mut().last_mut() {
+            query_inputs.extend_from_slice(inputs);
+        }
+    }
+
+    /// Runs the query computation, collecting the inputs it reads.
+    pub(crate) fn track_inputs<T>(&self, compute: impl FnOnce() -> T) -> (T, Vec<Input>) {
+        self.active_queries.borrow_mut().push(vec![]);
+        let value = compute();
+        let mut inputs = self.active_queries.borrow_mut().pop().unwrap_or_default();
+        inputs.sort_unstable();
+        inputs.dedup();
+        (value, inputs)
+    }
+
+    fn set_config(&mut self, config: Config) {
+        self.stdlib_files = Arc::new(
+            config
+                .stdlib_folder
+                .as_ref()
+                .map(|folder| io::read_move_files(folder.as_path()))
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        );
+        self.modules_folders_files = Arc::new(
+            config
+                .modules_folders
+                .iter()
+                .flat_map(io::read_move_files)
+                .collect(),
+        );
+        self.config = config;
     }
 
     pub fn apply_change(&mut self, change: AnalysisChange) {
+        self.revision += 1;
+        let revision = self.revision;
         if let Some(config) = change.config_changed {
-            self.config = config;
+            self.set_config(config);
+            Arc::make_mut(&mut self.changed_at).insert(Input::Config, revision);
+            // almost every query depends on the config, values of the previous one are dropped
+            // instead of being kept along with the ones of the files it doesn't load anymore
+            self.queries = Arc::new(QueryStorage::default());
         }
+        let changed_at = Arc::make_mut(&mut self.changed_at);
+        let available_files = Arc::make_mut(&mut self.available_files);
+        let source_files = Arc::make_mut(&mut self.source_files);
         for root_change in change.tracked_files_changed {
+            changed_at.insert(Input::File(root_change.fpath()), revision);
             match root_change {
                 RootChange::AddFile(fpath, text) => {
                     log::info!("AddFile: {:?}", fpath);
-                    self.available_files.insert(fpath, text);
+                    changed_at.insert(Input::FileSet, revision);
+                    let source_file = SourceFile::new(text.clone());
+                    source_files.insert(fpath, Arc::new(Mutex::new(source_file)));
+                    available_files.insert(fpath, text);
                 }
                 RootChange::ChangeFile(fpath, text) => {
                     log::info!("ChangeFile: {:?}", fpath);
-                    self.available_files.insert(fpath, text);
+                    if !available_files.contains_key(&fpath) {
+                        changed_at.insert(Input::FileSet, revision);
+                    }
+                    let source_file = SourceFile::new(text.clone());
+                    source_files.insert(fpath, Arc::new(Mutex::new(source_file)));
+                    available_files.insert(fpath, text);
+                }
+                RootChange::EditFile(fpath, text, edits) => {
+                    log::info!("EditFile: {:?}", fpath);
+                    if !available_files.contains_key(&fpath) {
+                        changed_at.insert(Input::FileSet, revision);
+                    }
+                    let source_file = match source_files.get(&fpath) {
+                        Some(source_file) => {
+                            source_file.lock().unwrap().edit(text.clone(), &edits)
+                        }
+                        None => SourceFile::new(text.clone()),
+                    };
+                    source_files.insert(fpath, Arc::new(Mutex::new(source_file)));
+                    available_files.insert(fpath, text);
                 }
                 RootChange::RemoveFile(fpath) => {
-                    if !self.available_files.contains_key(fpath) {
+                    if !available_files.contains_key(&fpath) {
                         log::warn!("RemoveFile: file {:?} does not exist", fpath);
                     }
                     log::info!("RemoveFile: {:?}", fpath);
-                    self.available_files.remove(fpath);
+                    changed_at.insert(Input::FileSet, revision);
+                    available_files.remove(&fpath);
+                    source_files.remove(&fpath);
+                    self.queries.evict_file(fpath);
                 }
             }
         }
@@ -111,19 +453,18 @@ impl RootDatabase {
 
     fn error_location_to_range(&self, loc: &dialects::shared::errors::Location) -> Result<Range> {
         let file = loc.fpath;
-        let text = match self.available_files.get(file) {
-            Some(text) => text.clone(),
+        let text = match self.any_file_text(file) {
+            Some(text) => text,
             None => {
                 anyhow::bail!(
-                    "File {:?} is not present in the available files {:#?}",
-                    file,
-                    &self.available_files.keys()
+                    "File {:?} is neither tracked nor one of the stdlib and modules folders files",
+                    file
                 );
             }
         };
         let file = File::new(text);
-        let start_pos = file.position(loc.span.0).unwrap();
-        let end_pos = file.position(loc.span.1).unwrap();
+        let start_pos = file.position(loc.span.0)?;
+        let end_pos = file.position(loc.span.1)?;
         Ok(Range::new(start_pos, end_pos))
     }
 
@@ -136,14 +477,27 @@ impl RootDatabase {
         } = error.parts[0].to_owned();
         let mut diagnostic = {
             let range = self.error_location_to_range(&prim_location)?;
-            Diagnostic::new_simple(range, message)
+            let severity = match error.severity {
+                Severity::Error => DiagnosticSeverity::Error,
+                Severity::Warning => DiagnosticSeverity::Warning,
+                Severity::Info => DiagnosticSeverity::Information,
+                Severity::Hint => DiagnosticSeverity::Hint,
+            };
+            let code = error.code.clone().map(NumberOrString::String);
+            Diagnostic::new(range, Some(severity), code, None, message, None, None)
         };
 
         // first error is an actual one, others are related info
         if error.parts.len() > 1 {
             let mut related_info = vec![];
             for CompilerErrorPart { location, message } in error.parts[1..].iter() {
-                let range = self.error_location_to_range(location)?;
+                let range = match self.error_location_to_range(location) {
+                    Ok(range) => range,
+                    Err(err) => {
+                        log::warn!("Related information {:?} is skipped: {}", location, err);
+                        continue;
+                    }
+                };
                 let related_fpath = location.fpath;
                 let file_uri = Url::from_file_path(related_fpath)
                     .unwrap_or_else(|_| panic!("Cannot build Url from path {:?}", related_fpath));
@@ -159,9 +513,9 @@ impl RootDatabase {
         Ok(FileDiagnostic::new(prim_location.fpath, diagnostic))
     }
 
-    fn is_fpath_for_a_module(&self, fpath: MoveFilePath) -> bool {
-        for module_folder in self.config.modules_folders.iter() {
-            if fpath.starts_with(module_folder.to_str().unwrap()) {
+    pub fn is_fpath_for_a_module(&self, fpath: FileId) -> bool {
+        for module_folder in self.config().modules_folders.iter() {
+            if fpath.path().starts_with(module_folder.to_str().unwrap()) {
                 return true;
             }
         }
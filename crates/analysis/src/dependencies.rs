@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+
+use syntax::ast::{SourceFile, UseDecl};
+
+use crate::db::RootDatabase;
+use crate::items::{addresses_equal, normalize_address};
+use crate::utils::tree::{descendants, node_text};
+use utils::FileId;
+
+/// Module the file refers to, with `use` declaration or with an address qualified path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleRef {
+    pub address: String,
+    pub name: String,
+}
+
+impl ModuleRef {
+    pub fn new(address: &str, name: &str) -> ModuleRef {
+        ModuleRef {
+            address: address.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn is_at(&self, address: &str, name: &str) -> bool {
+        self.name == name && addresses_equal(&self.address, address)
+    }
+}
+
+/// Tracked files referring to the module, keyed by the normalized address and the name of it.
+pub type ModuleDependents = HashMap<(String, String), Vec<FileId>>;
+
+pub fn module_dependents(db: &RootDatabase) -> ModuleDependents {
+    let mut dependents = ModuleDependents::new();
+    for fpath in db.tracked_files() {
+        for module_ref in db.file_module_refs(fpath).iter() {
+            dependents
+                .entry((
+                    normalize_address(&module_ref.address),
+                    module_ref.name.clone(),
+                ))
+                .or_default()
+                .push(fpath);
+        }
+    }
+    dependents
+}
+
+pub fn module_refs(source_file: &SourceFile) -> Vec<ModuleRef> {
+    let source = source_file.source();
+    let mut refs = vec![];
+    for node in descendants(source_file.tree.root_node()) {
+        let module_ref = match node.kind() {
+            "use_decl" => {
+                let use_decl = UseDecl::new(source, node);
+                match (use_decl.address(), use_decl.module()) {
+                    (Some(address), Some(name)) => ModuleRef::new(address, name),
+                    _ => continue,
+                }
+            }
+            "module_access" => {
+                match (
+                    node.child_by_field_name("address"),
+                    node.child_by_field_name("module"),
+                ) {
+                    (Some(address), Some(name)) => {
+                        ModuleRef::new(node_text(source, address), node_text(source, name))
+                    }
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        if !refs.contains(&module_ref) {
+            refs.push(module_ref);
+        }
+    }
+    refs
+}
+
+/// Tracked files, which might get different diagnostics after the `changed` ones change:
+/// the changed files themselves, and the ones transitively using modules declared in them.
+pub fn dependent_files(db: &RootDatabase, changed: &[FileId]) -> Vec<FileId> {
+    let module_dependents = db.module_dependents();
+    let mut dependents: Vec<FileId> = changed.to_vec();
+    let mut visited: HashSet<FileId> = changed.iter().copied().collect();
+    let mut queue = changed.to_vec();
+    while let Some(fpath) = queue.pop() {
+        if db.file_text(fpath).is_none() {
+            continue;
+        }
+        for module in db.file_modules(fpath).iter() {
+            let module_key = (normalize_address(&module.address), module.name.clone());
+            for dependent in module_dependents.get(&module_key).into_iter().flatten() {
+                if visited.insert(*dependent) {
+                    dependents.push(*dependent);
+                    queue.push(*dependent);
+                }
+            }
+        }
+    }
+    dependents
+}
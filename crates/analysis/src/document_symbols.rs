@@ -0,0 +1,106 @@
+use lsp_types::{DocumentSymbol, SymbolKind};
+use syntax::ast::Definition;
+
+use crate::db::RootDatabase;
+use crate::items::{module_def, Item, ItemKind};
+use crate::utils::tree::node_range;
+use utils::FileId;
+
+/// Outline of the file: address blocks, modules with their structs and functions, scripts.
+pub fn document_symbols(db: &RootDatabase, fpath: FileId) -> Vec<DocumentSymbol> {
+    let source_file = match db.any_source_file(fpath) {
+        Some(source_file) => source_file,
+        None => return vec![],
+    };
+    let source = source_file.source();
+    let sender = &db.config().sender().normalized_original;
+
+    let mut symbols = vec![];
+    for definition in source_file.definitions() {
+        match definition {
+            Definition::AddressBlock(address_block) => {
+                let address = match address_block.address() {
+                    Some(address) => address,
+                    None => continue,
+                };
+                let modules = address_block
+                    .modules()
+                    .iter()
+                    .filter_map(|module| module_def(fpath, source, module, address))
+                    .map(|module_def| item_symbol(&module_def.def, &module_def.items))
+                    .collect();
+                let address_node = address_block.node.child_by_field_name("address");
+                symbols.push(DocumentSymbol {
+                    name: address.to_string(),
+                    detail: None,
+                    kind: SymbolKind::Namespace,
+                    deprecated: None,
+                    range: node_range(source, address_block.node),
+                    selection_range: node_range(
+                        source,
+                        address_node.unwrap_or(address_block.node),
+                    ),
+                    children: Some(modules),
+                });
+            }
+            Definition::ModuleBlock(module) => {
+                if let Some(module_def) = module_def(fpath, source, &module, sender) {
+                    symbols.push(item_symbol(&module_def.def, &module_def.items));
+                }
+            }
+            Definition::ScriptBlock(script) => {
+                let main = script.main_function().and_then(|main| {
+                    Item::new(ItemKind::Function, fpath, source, main.node, None)
+                });
+                let children = main.iter().map(|main| item_symbol(main, &[])).collect();
+                let range = node_range(source, script.node);
+                symbols.push(DocumentSymbol {
+                    name: "script".to_string(),
+                    detail: None,
+                    kind: SymbolKind::Module,
+                    deprecated: None,
+                    range,
+                    selection_range: main.map(|main| main.name_range).unwrap_or(range),
+                    children: Some(children),
+                });
+            }
+        }
+    }
+    symbols
+}
+
+fn item_symbol(item: &Item, children: &[Item]) -> DocumentSymbol {
+    let kind = match item.kind {
+        ItemKind::Module => SymbolKind::Module,
+        ItemKind::Function | ItemKind::NativeFunction | ItemKind::SpecFunction => {
+            SymbolKind::Function
+        }
+        ItemKind::Struct | ItemKind::Resource | ItemKind::NativeStruct => SymbolKind::Struct,
+        ItemKind::Field => SymbolKind::Field,
+        ItemKind::Local | ItemKind::Parameter => SymbolKind::Variable,
+        ItemKind::TypeParameter => SymbolKind::TypeParameter,
+    };
+    let children = if item.kind.is_struct() {
+        &item.fields
+    } else {
+        children
+    };
+    DocumentSymbol {
+        name: item.name.clone(),
+        detail: item.signature.lines().next().map(str::to_string),
+        kind,
+        deprecated: None,
+        range: item.range,
+        selection_range: item.name_range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(
+                children
+                    .iter()
+                    .map(|child| item_symbol(child, &[]))
+                    .collect(),
+            )
+        },
+    }
+}
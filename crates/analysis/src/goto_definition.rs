@@ -0,0 +1,10 @@
+use crate::db::{FilePosition, FileRange, RootDatabase};
+use crate::resolve::resolve;
+
+pub fn goto_definition(db: &RootDatabase, position: FilePosition) -> Option<FileRange> {
+    let item = resolve(db, &position)?;
+    Some(FileRange {
+        fpath: item.fpath,
+        range: item.name_range,
+    })
+}
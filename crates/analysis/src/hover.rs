@@ -0,0 +1,37 @@
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind};
+
+use crate::db::{FilePosition, RootDatabase};
+use crate::infer::item_type;
+use crate::items::{Item, ItemKind};
+use crate::resolve::resolve_position;
+
+pub fn hover(db: &RootDatabase, position: FilePosition) -> Option<Hover> {
+    let index = db.item_index();
+    let (mut item, ident_range) = resolve_position(db, &index, &position)?;
+    // type of the local without annotation is inferred from its initializer
+    if item.kind == ItemKind::Local && !item.signature.contains(':') {
+        if let Some(ty) = item_type(db, &item).filter(|ty| !ty.is_unknown()) {
+            item.signature = format!("{}: {}", item.signature, ty);
+        }
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: hover_text(&item),
+        }),
+        range: Some(ident_range),
+    })
+}
+
+fn hover_text(item: &Item) -> String {
+    let mut text = String::new();
+    if let Some(container) = &item.container {
+        text.push_str(&format!("```move\n{}\n```\n\n", container));
+    }
+    text.push_str(&format!("```move\n{}\n```", item.signature));
+    if let Some(docs) = &item.docs {
+        text.push_str("\n\n---\n\n");
+        text.push_str(docs);
+    }
+    text
+}
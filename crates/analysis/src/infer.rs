@@ -0,0 +1,796 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use lsp_types::{Position, Range};
+use syntax::ast::{
+    BinaryExpr, BindUnpack, BorrowExpr, CallExpr, DotExpr, Expr, LetStatement, Literal, PackExpr,
+    SourceFile, Term, Type, UnaryExpr,
+};
+use tree_sitter::Node;
+
+use crate::db::{FilePosition, RootDatabase};
+use crate::items::{Item, ItemIndex, ItemKind};
+use crate::resolve::Resolver;
+use crate::utils::tree::{ancestors, node_range, node_text, position_to_point};
+use utils::FileId;
+
+/// Type of the expression, as far as it could be inferred from the source alone.
+#[derive(Debug, Clone)]
+pub enum Ty {
+    U8,
+    U64,
+    U128,
+    Bool,
+    Address,
+    Signer,
+    Vector(Box<Ty>),
+    /// Struct declaration, along with the type arguments.
+    Struct(Box<Item>, Vec<Ty>),
+    Ref {
+        is_mut: bool,
+        ty: Box<Ty>,
+    },
+    /// Unit is the empty tuple.
+    Tuple(Vec<Ty>),
+    TypeParam(String),
+    Unknown,
+}
+
+impl Ty {
+    pub fn unit() -> Ty {
+        Ty::Tuple(vec![])
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Ty::Unknown)
+    }
+
+    /// Type behind the references.
+    pub fn strip_refs(&self) -> &Ty {
+        match self {
+            Ty::Ref { ty, .. } => ty.strip_refs(),
+            ty => ty,
+        }
+    }
+
+    fn subst(&self, substs: &HashMap<String, Ty>) -> Ty {
+        match self {
+            Ty::TypeParam(name) => substs.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Ty::Vector(item) => Ty::Vector(Box::new(item.subst(substs))),
+            Ty::Struct(item, args) => Ty::Struct(
+                item.clone(),
+                args.iter().map(|arg| arg.subst(substs)).collect(),
+            ),
+            Ty::Ref { is_mut, ty } => Ty::Ref {
+                is_mut: *is_mut,
+                ty: Box::new(ty.subst(substs)),
+            },
+            Ty::Tuple(items) => Ty::Tuple(items.iter().map(|item| item.subst(substs)).collect()),
+            ty => ty.clone(),
+        }
+    }
+
+    /// Collects the types of the type parameters in `self`, which turn it into `actual`.
+    fn unify(&self, actual: &Ty, substs: &mut HashMap<String, Ty>) {
+        match (self, actual) {
+            (_, Ty::Unknown) => {}
+            (Ty::TypeParam(name), _) => {
+                substs.entry(name.clone()).or_insert_with(|| actual.clone());
+            }
+            (Ty::Vector(expected), Ty::Vector(actual)) => expected.unify(actual, substs),
+            (Ty::Ref { ty: expected, .. }, Ty::Ref { ty: actual, .. }) => {
+                expected.unify(actual, substs)
+            }
+            (Ty::Struct(expected, expected_args), Ty::Struct(actual, actual_args))
+                if expected.fpath == actual.fpath && expected.name_range == actual.name_range =>
+            {
+                for (expected, actual) in expected_args.iter().zip(actual_args) {
+                    expected.unify(actual, substs);
+                }
+            }
+            (Ty::Tuple(expected), Ty::Tuple(actual)) => {
+                for (expected, actual) in expected.iter().zip(actual) {
+                    expected.unify(actual, substs);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ty::U8 => write!(f, "u8"),
+            Ty::U64 => write!(f, "u64"),
+            Ty::U128 => write!(f, "u128"),
+            Ty::Bool => write!(f, "bool"),
+            Ty::Address => write!(f, "address"),
+            Ty::Signer => write!(f, "signer"),
+            Ty::Vector(item) => write!(f, "vector<{}>", item),
+            Ty::Struct(item, args) if args.is_empty() => write!(f, "{}", item.name),
+            Ty::Struct(item, args) => write!(f, "{}<{}>", item.name, join(args)),
+            Ty::Ref { is_mut: true, ty } => write!(f, "&mut {}", ty),
+            Ty::Ref { is_mut: false, ty } => write!(f, "&{}", ty),
+            Ty::Tuple(items) => write!(f, "({})", join(items)),
+            Ty::TypeParam(name) => write!(f, "{}", name),
+            Ty::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+fn join(types: &[Ty]) -> String {
+    types
+        .iter()
+        .map(Ty::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Type of the innermost expression at the position.
+pub fn expr_type_at(db: &RootDatabase, position: &FilePosition) -> Option<Ty> {
+    let source_file = db.any_source_file(position.fpath)?;
+    let index = db.item_index();
+    let inference = TypeInference::new(db, &index, position.fpath, &source_file);
+
+    let (line, column) = position.pos;
+    let point = position_to_point(
+        source_file.source(),
+        Position::new(line as u64, column as u64),
+    );
+    let node = source_file
+        .tree
+        .root_node()
+        .named_descendant_for_point_range(point, point)?;
+    let expr = std::iter::once(node)
+        .chain(ancestors(node))
+        .find(|node| is_expr(node.kind()))?;
+    // name of the called function or of the packed struct is not a value on its own
+    let expr = match expr.parent() {
+        Some(parent) if matches!(parent.kind(), "call_expression" | "pack_expression") => parent,
+        _ => expr,
+    };
+    Some(inference.expr_type(expr))
+}
+
+/// Type of the local variable, function parameter or struct field.
+pub fn item_type(db: &RootDatabase, item: &Item) -> Option<Ty> {
+    let index = db.item_index();
+    with_node_at(
+        db,
+        &index,
+        item.fpath,
+        item.name_range,
+        |inference, name_node| inference.declared_type(item.kind, name_node),
+    )
+}
+
+/// Runs `f` on the node at `range` of the file.
+fn with_node_at<R>(
+    db: &RootDatabase,
+    index: &ItemIndex,
+    fpath: FileId,
+    range: Range,
+    f: impl for<'b> FnOnce(&TypeInference<'b>, Node<'b>) -> Option<R>,
+) -> Option<R> {
+    let source_file = db.any_source_file(fpath)?;
+    let inference = TypeInference::new(db, index, fpath, &source_file);
+    let node = inference.node_at(range)?;
+    f(&inference, node)
+}
+
+fn is_expr(kind: &str) -> bool {
+    matches!(
+        kind,
+        "lambda_expression"
+            | "if_expression"
+            | "while_expression"
+            | "loop_expression"
+            | "return_expression"
+            | "abort_expression"
+            | "assign_expression"
+            | "binary_expression"
+            | "unary_expression"
+            | "borrow_expression"
+            | "dereference_expression"
+            | "move_or_copy_expression"
+            | "break_expression"
+            | "continue_expression"
+            | "name_expression"
+            | "pack_expression"
+            | "call_expression"
+            | "unit_expression"
+            | "expression_list"
+            | "cast_expression"
+            | "annotate_expression"
+            | "dot_expression"
+            | "index_expression"
+            | "block"
+            | "spec_block"
+            | "address_literal"
+            | "bool_literal"
+            | "num_literal"
+            | "byte_string_literal"
+    )
+}
+
+fn literal_type(literal: &Literal) -> Ty {
+    match literal {
+        Literal::Address(_) => Ty::Address,
+        Literal::Bool(_) => Ty::Bool,
+        Literal::ByteString(_) => Ty::Vector(Box::new(Ty::U8)),
+        Literal::Num(num) if num.ends_with("u8") => Ty::U8,
+        Literal::Num(num) if num.ends_with("u128") => Ty::U128,
+        // literals without the suffix default to `u64`
+        Literal::Num(_) => Ty::U64,
+    }
+}
+
+/// Result of the call of the builtin function, like `borrow_global<T>(addr)`.
+fn builtin_call_type(name: &str, type_args: &[Ty], arg_types: &[Ty]) -> Ty {
+    let type_arg = || type_args.first().cloned().unwrap_or(Ty::Unknown);
+    match name {
+        "borrow_global" | "borrow_global_mut" => Ty::Ref {
+            is_mut: name == "borrow_global_mut",
+            ty: Box::new(type_arg()),
+        },
+        "move_from" => type_arg(),
+        "exists" => Ty::Bool,
+        "move_to" | "move_to_sender" => Ty::unit(),
+        "freeze" => match arg_types.first() {
+            Some(Ty::Ref { ty, .. }) => Ty::Ref {
+                is_mut: false,
+                ty: ty.clone(),
+            },
+            _ => Ty::Unknown,
+        },
+        _ => Ty::Unknown,
+    }
+}
+
+/// Infers types of the expressions of a single file.
+pub struct TypeInference<'a> {
+    db: &'a RootDatabase,
+    index: &'a ItemIndex,
+    resolver: Resolver<'a>,
+    source_file: &'a SourceFile,
+}
+
+impl<'a> TypeInference<'a> {
+    pub fn new(
+        db: &'a RootDatabase,
+        index: &'a ItemIndex,
+        fpath: FileId,
+        source_file: &'a SourceFile,
+    ) -> TypeInference<'a> {
+        let sender = &db.config().sender().normalized_original;
+        TypeInference {
+            db,
+            index,
+            resolver: Resolver::new(index, fpath, source_file.source(), sender),
+            source_file,
+        }
+    }
+
+    /// Type of the expression node, `Ty::Unknown` for anything else.
+    pub fn expr_type(&self, node: Node<'a>) -> Ty {
+        if !is_expr(node.kind()) {
+            return Ty::Unknown;
+        }
+        match Expr::new(self.source(), node) {
+            Expr::If(if_expr) => {
+                if if_expr.node.child_by_field_name("ef").is_none() {
+                    return Ty::unit();
+                }
+                // one of the branches might diverge
+                match self.child_expr_type(if_expr.node, "et") {
+                    Ty::Unknown => self.child_expr_type(if_expr.node, "ef"),
+                    ty => ty,
+                }
+            }
+            Expr::While(_) | Expr::Assign(_) => Ty::unit(),
+            Expr::Binary(binary) => self.binary_type(&binary),
+            Expr::Unary(unary) => self.unary_type(&unary),
+            Expr::Lambda(_) | Expr::Loop(_) | Expr::Return(_) | Expr::Abort(_) => Ty::Unknown,
+        }
+    }
+
+    fn binary_type(&self, binary: &BinaryExpr) -> Ty {
+        match binary.operator().unwrap_or_default() {
+            // shift amount is `u8`, whatever the type of the shifted value
+            "<<" | ">>" => self.child_expr_type(binary.node, "lhs"),
+            "+" | "-" | "*" | "/" | "%" | "&" | "|" | "^" => {
+                match self.child_expr_type(binary.node, "lhs") {
+                    Ty::Unknown => self.child_expr_type(binary.node, "rhs"),
+                    ty => ty,
+                }
+            }
+            ".." => Ty::Unknown,
+            _ => Ty::Bool,
+        }
+    }
+
+    fn unary_type(&self, unary: &UnaryExpr) -> Ty {
+        match unary {
+            UnaryExpr::Not(_) => Ty::Bool,
+            UnaryExpr::Borrow(borrow) => self.borrow_type(borrow),
+            UnaryExpr::Deref(deref) => match self.child_expr_type(deref.node, "exp") {
+                Ty::Ref { ty, .. } => *ty,
+                _ => Ty::Unknown,
+            },
+            UnaryExpr::MoveOrCopy(move_or_copy) => move_or_copy
+                .node
+                .child_by_field_name("exp")
+                .map(|ident| self.name_type(ident))
+                .unwrap_or(Ty::Unknown),
+            UnaryExpr::Term(term) => self.term_type(term),
+        }
+    }
+
+    fn borrow_type(&self, borrow: &BorrowExpr) -> Ty {
+        Ty::Ref {
+            is_mut: borrow.is_mut(),
+            ty: Box::new(self.child_expr_type(borrow.node, "exp")),
+        }
+    }
+
+    fn term_type(&self, term: &Term) -> Ty {
+        match term {
+            Term::Name(name) => name
+                .node
+                .named_child(0)
+                .and_then(last_named_child)
+                .map(|ident| self.name_type(ident))
+                .unwrap_or(Ty::Unknown),
+            Term::Pack(pack) => self.pack_type(pack),
+            Term::Call(call) => self.call_type(call),
+            Term::Literal(literal) => literal_type(literal),
+            Term::Unit(_) => Ty::unit(),
+            Term::ExprList(list) => {
+                let mut items: Vec<_> = named_children(list.node)
+                    .into_iter()
+                    .map(|item| self.expr_type(item))
+                    .collect();
+                if items.len() == 1 {
+                    items.remove(0)
+                } else {
+                    Ty::Tuple(items)
+                }
+            }
+            Term::Block(block) => {
+                // value of the block is the trailing expression without `;`
+                let node = block.node;
+                let last_item = node.named_child(node.named_child_count().saturating_sub(1));
+                let before_brace = node.child(node.child_count().saturating_sub(2));
+                match last_item {
+                    Some(item) if Some(item) == before_brace => self.expr_type(item),
+                    _ => Ty::unit(),
+                }
+            }
+            Term::Dot(dot) => self.dot_type(dot),
+            Term::Index(index) => match self.child_expr_type(index.node, "e").strip_refs() {
+                Ty::Vector(item) => *item.clone(),
+                _ => Ty::Unknown,
+            },
+            Term::Cast(cast) => cast
+                .ty()
+                .map(|ty| self.lower_type(&ty))
+                .unwrap_or(Ty::Unknown),
+            Term::Annotate(annotate) => annotate
+                .ty()
+                .map(|ty| self.lower_type(&ty))
+                .unwrap_or(Ty::Unknown),
+            Term::Break(_) | Term::Continue(_) | Term::Spec(_) => Ty::Unknown,
+        }
+    }
+
+    fn dot_type(&self, dot: &DotExpr) -> Ty {
+        let field = match dot.field() {
+            Some(field) => field,
+            None => return Ty::Unknown,
+        };
+        // fields of the borrowed struct are accessed without the dereference
+        match self.child_expr_type(dot.node, "e").strip_refs() {
+            Ty::Struct(item, args) => self.struct_field_type(item, args, field),
+            _ => Ty::Unknown,
+        }
+    }
+
+    fn pack_type(&self, pack: &PackExpr) -> Ty {
+        let name = match pack.name() {
+            Some(name) => name,
+            None => return Ty::Unknown,
+        };
+        let item = match self.resolve_name_expr(name.node) {
+            Some(item) if item.kind.is_struct() => item,
+            _ => return Ty::Unknown,
+        };
+        let type_params = self.type_params(&item);
+        let explicit_args = self.type_args(name.node);
+
+        let substs = if explicit_args.is_empty() {
+            let mut substs = HashMap::new();
+            for field_assignment in pack.body().unwrap_or_default() {
+                let field = match field_assignment.field() {
+                    Some(field) => field,
+                    None => continue,
+                };
+                let actual = match field_assignment.node.child_by_field_name("exp") {
+                    Some(exp) => self.expr_type(exp),
+                    // `T { field }` takes the local variable `field`
+                    None => field_assignment
+                        .node
+                        .child_by_field_name("field")
+                        .and_then(|ident| self.resolver.resolve_shorthand_local(ident))
+                        .map(|local| self.local_type(&local))
+                        .unwrap_or(Ty::Unknown),
+                };
+                if let Some(expected) = self.declared_field_type(&item, field) {
+                    expected.unify(&actual, &mut substs);
+                }
+            }
+            substs
+        } else {
+            type_params.iter().cloned().zip(explicit_args).collect()
+        };
+        let args = type_params
+            .iter()
+            .map(|param| substs.get(param).cloned().unwrap_or(Ty::Unknown))
+            .collect();
+        Ty::Struct(Box::new(item), args)
+    }
+
+    fn call_type(&self, call: &CallExpr) -> Ty {
+        let name = match call.name() {
+            Some(name) => name,
+            None => return Ty::Unknown,
+        };
+        let explicit_args = self.type_args(name.node);
+        let arg_types: Vec<_> = call
+            .node
+            .child_by_field_name("args")
+            .map(named_children)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|arg| self.expr_type(arg))
+            .collect();
+
+        let item = match self.resolve_name_expr(name.node) {
+            Some(item) if item.kind.is_spec_callable() => item,
+            Some(_) => return Ty::Unknown,
+            None => {
+                return match name.fully_qual_name() {
+                    Some(module_access) if module_access.module().is_none() => builtin_call_type(
+                        module_access.name().unwrap_or_default(),
+                        &explicit_args,
+                        &arg_types,
+                    ),
+                    _ => Ty::Unknown,
+                }
+            }
+        };
+
+        let signature = with_node_at(
+            self.db,
+            self.index,
+            item.fpath,
+            item.range,
+            |inference, function| Some(inference.function_signature(function)),
+        );
+        let (type_params, param_types, return_type) = match signature {
+            Some(signature) => signature,
+            None => return Ty::Unknown,
+        };
+        let substs = if explicit_args.is_empty() {
+            let mut substs = HashMap::new();
+            for (expected, actual) in param_types.iter().zip(&arg_types) {
+                expected.unify(actual, &mut substs);
+            }
+            substs
+        } else {
+            type_params.into_iter().zip(explicit_args).collect()
+        };
+        return_type.subst(&substs)
+    }
+
+    /// Type parameters, parameter types and return type of the function declaration.
+    fn function_signature(&self, function: Node<'a>) -> (Vec<String>, Vec<Ty>, Ty) {
+        let type_params = self.type_param_names(function);
+        let param_types = function
+            .child_by_field_name("params")
+            .map(named_children)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|param| param.kind() == "function_parameter")
+            .map(|param| self.declared_type_of(param))
+            .collect();
+        let return_type = match function.child_by_field_name("return_type") {
+            Some(return_type) => self.lower_type_node(return_type),
+            None => Ty::unit(),
+        };
+        (type_params, param_types, return_type)
+    }
+
+    /// Type of the local, parameter or field, declared by the `name_node`.
+    fn declared_type(&self, kind: ItemKind, name_node: Node<'a>) -> Option<Ty> {
+        match kind {
+            ItemKind::Local => Some(self.binder_type(name_node)),
+            ItemKind::Parameter | ItemKind::Field => {
+                Some(self.declared_type_of(name_node.parent()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Type of the local variable or parameter, which the identifier refers to.
+    fn name_type(&self, ident: Node<'a>) -> Ty {
+        match self.resolver.resolve(ident) {
+            Some(item) => self.local_type(&item),
+            None => Ty::Unknown,
+        }
+    }
+
+    fn local_type(&self, item: &Item) -> Ty {
+        if !matches!(item.kind, ItemKind::Local | ItemKind::Parameter) {
+            return Ty::Unknown;
+        }
+        // locals are declared in the same file
+        self.node_at(item.name_range)
+            .and_then(|name_node| self.declared_type(item.kind, name_node))
+            .unwrap_or(Ty::Unknown)
+    }
+
+    /// Type of the variable, introduced by the `binder` of the `let` statement.
+    fn binder_type(&self, binder: Node<'a>) -> Ty {
+        let let_node = match ancestors(binder).find(|node| node.kind() == "let_statement") {
+            Some(let_node) => let_node,
+            None => return Ty::Unknown,
+        };
+        let let_statement = LetStatement::new(self.source(), let_node);
+        let bound_type = match let_statement.typ() {
+            Some(typ) => self.lower_type(&typ),
+            None => self.child_expr_type(let_node, "exp"),
+        };
+        let binds = let_node
+            .child_by_field_name("binds")
+            .map(named_children)
+            .unwrap_or_default();
+        let position = match binds.iter().position(|bind| contains(*bind, binder)) {
+            Some(position) => position,
+            None => return Ty::Unknown,
+        };
+        if binds.len() == 1 {
+            return self.bind_type(binds[position], bound_type, binder);
+        }
+        match bound_type {
+            Ty::Tuple(items) => match items.get(position) {
+                Some(item) => self.bind_type(binds[position], item.clone(), binder),
+                None => Ty::Unknown,
+            },
+            _ => Ty::Unknown,
+        }
+    }
+
+    fn bind_type(&self, bind: Node<'a>, bound_type: Ty, binder: Node<'a>) -> Ty {
+        // `bind_var` and the identifier inside of it share the range
+        if (bind.start_byte(), bind.end_byte()) == (binder.start_byte(), binder.end_byte()) {
+            return bound_type;
+        }
+        if bind.kind() != "bind_unpack" {
+            return Ty::Unknown;
+        }
+        // unpacked reference binds references to the fields
+        let (ref_mut, struct_type) = match bound_type {
+            Ty::Ref { is_mut, ty } => (Some(is_mut), *ty),
+            ty => (None, ty),
+        };
+        let (item, args) = match struct_type {
+            Ty::Struct(item, args) => (item, args),
+            _ => return Ty::Unknown,
+        };
+        let unpack = BindUnpack::new(self.source(), bind);
+        let bind_field = unpack
+            .bind_fields()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|bind_field| contains(bind_field.node, binder));
+        let bind_field = match bind_field {
+            Some(bind_field) => bind_field,
+            None => return Ty::Unknown,
+        };
+        let field_type = match bind_field.field() {
+            Some(field) => self.struct_field_type(&item, &args, field),
+            None => Ty::Unknown,
+        };
+        let field_type = match ref_mut {
+            Some(is_mut) => Ty::Ref {
+                is_mut,
+                ty: Box::new(field_type),
+            },
+            None => field_type,
+        };
+        match bind_field.node.child_by_field_name("bind") {
+            Some(bind) => self.bind_type(bind, field_type, binder),
+            // `T { field }` binds variable `field`
+            None => field_type,
+        }
+    }
+
+    /// Type of the struct field, with type parameters replaced by the `args`.
+    fn struct_field_type(&self, item: &Item, args: &[Ty], field: &str) -> Ty {
+        let field_type = match self.declared_field_type(item, field) {
+            Some(field_type) => field_type,
+            None => return Ty::Unknown,
+        };
+        let substs = self
+            .type_params(item)
+            .into_iter()
+            .zip(args.iter().cloned())
+            .collect();
+        field_type.subst(&substs)
+    }
+
+    fn declared_field_type(&self, item: &Item, field: &str) -> Option<Ty> {
+        let field = item.field(field)?;
+        with_node_at(
+            self.db,
+            self.index,
+            field.fpath,
+            field.range,
+            |inference, field_annotation| Some(inference.declared_type_of(field_annotation)),
+        )
+    }
+
+    fn type_params(&self, item: &Item) -> Vec<String> {
+        with_node_at(
+            self.db,
+            self.index,
+            item.fpath,
+            item.range,
+            |inference, decl| Some(inference.type_param_names(decl)),
+        )
+        .unwrap_or_default()
+    }
+
+    fn type_param_names(&self, decl: Node<'a>) -> Vec<String> {
+        decl.child_by_field_name("type_parameters")
+            .map(named_children)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|type_param| type_param.named_child(0))
+            .map(|name| self.text(name).to_string())
+            .collect()
+    }
+
+    /// Explicit type arguments of the name expression, like `u8` in `f<u8>()`.
+    fn type_args(&self, name_expr: Node<'a>) -> Vec<Ty> {
+        name_expr
+            .child_by_field_name("type_arguments")
+            .map(named_children)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ty| self.lower_type_node(ty))
+            .collect()
+    }
+
+    fn resolve_name_expr(&self, name_expr: Node<'a>) -> Option<Item> {
+        let module_access = name_expr.named_child(0)?;
+        self.resolver.resolve(last_named_child(module_access)?)
+    }
+
+    /// Type of the type node, unknown for the nodes of other kinds in error-recovered trees.
+    fn lower_type_node(&self, node: Node<'a>) -> Ty {
+        Type::cast(self.source(), node)
+            .map(|ty| self.lower_type(&ty))
+            .unwrap_or(Ty::Unknown)
+    }
+
+    /// Type, written in the source.
+    pub fn lower_type(&self, ty: &Type) -> Ty {
+        match ty {
+            Type::ApplyType(apply_type) => {
+                let module_access = match apply_type.module_access() {
+                    Some(module_access) => module_access,
+                    None => return Ty::Unknown,
+                };
+                let type_args: Vec<_> = apply_type
+                    .type_arguments()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|arg| self.lower_type(arg))
+                    .collect();
+                let ident = match last_named_child(module_access.node) {
+                    Some(ident) => ident,
+                    None => return Ty::Unknown,
+                };
+                if let Some(item) = self.resolver.resolve(ident) {
+                    return match item.kind {
+                        ItemKind::TypeParameter => Ty::TypeParam(item.name),
+                        kind if kind.is_struct() => Ty::Struct(Box::new(item), type_args),
+                        _ => Ty::Unknown,
+                    };
+                }
+                if module_access.module().is_some() || module_access.address().is_some() {
+                    return Ty::Unknown;
+                }
+                match self.text(ident) {
+                    "u8" => Ty::U8,
+                    "u64" => Ty::U64,
+                    "u128" => Ty::U128,
+                    "bool" => Ty::Bool,
+                    "address" => Ty::Address,
+                    "signer" => Ty::Signer,
+                    "vector" => Ty::Vector(Box::new(
+                        type_args.into_iter().next().unwrap_or(Ty::Unknown),
+                    )),
+                    _ => Ty::Unknown,
+                }
+            }
+            Type::RefType(ref_type) => Ty::Ref {
+                is_mut: ref_type.text().starts_with("&mut"),
+                ty: Box::new(
+                    ref_type
+                        .typ()
+                        .map(|ty| self.lower_type(&ty))
+                        .unwrap_or(Ty::Unknown),
+                ),
+            },
+            Type::TupleType(tuple_type) => Ty::Tuple(
+                tuple_type
+                    .items()
+                    .iter()
+                    .map(|item| self.lower_type(item))
+                    .collect(),
+            ),
+            Type::FunctionType(_) => Ty::Unknown,
+        }
+    }
+
+    /// Type of the expression in the `field` of the node.
+    fn child_expr_type(&self, node: Node<'a>, field: &str) -> Ty {
+        node.child_by_field_name(field)
+            .map(|exp| self.expr_type(exp))
+            .unwrap_or(Ty::Unknown)
+    }
+
+    /// Type in the `type` field of the declaration, like the one of `a: u8` parameter.
+    fn declared_type_of(&self, decl: Node<'a>) -> Ty {
+        decl.child_by_field_name("type")
+            .map(|ty| self.lower_type_node(ty))
+            .unwrap_or(Ty::Unknown)
+    }
+
+    fn node_at(&self, range: Range) -> Option<Node<'a>> {
+        self.source_file
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(
+                position_to_point(self.source(), range.start),
+                position_to_point(self.source(), range.end),
+            )
+            .filter(|node| node_range(self.source(), *node) == range)
+    }
+
+    fn source(&self) -> &'a str {
+        self.source_file.source()
+    }
+
+    fn text(&self, node: Node) -> &'a str {
+        node_text(self.source(), node)
+    }
+}
+
+fn named_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|child| child.kind() != "line_comment")
+        .collect()
+}
+
+fn last_named_child(node: Node) -> Option<Node> {
+    node.named_child(node.named_child_count().checked_sub(1)?)
+}
+
+fn contains(node: Node, inner: Node) -> bool {
+    node.start_byte() <= inner.start_byte() && inner.end_byte() <= node.end_byte()
+}
@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+
+use lsp_types::Range;
+use syntax::ast::{Definition, Module, ModuleItem, SourceFile};
+use tree_sitter::Node;
+
+use crate::db::RootDatabase;
+use crate::utils::tree::{node_range, node_text, single_line};
+use utils::FileId;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ItemKind {
+    Module,
+    Function,
+    NativeFunction,
+    Struct,
+    Resource,
+    NativeStruct,
+    SpecFunction,
+    Field,
+    Local,
+    Parameter,
+    TypeParameter,
+}
+
+impl ItemKind {
+    /// Function, callable from the code.
+    pub fn is_function(&self) -> bool {
+        matches!(self, ItemKind::Function | ItemKind::NativeFunction)
+    }
+
+    /// Function, callable from the specifications: code functions along with the spec ones.
+    pub fn is_spec_callable(&self) -> bool {
+        self.is_function() || *self == ItemKind::SpecFunction
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(
+            self,
+            ItemKind::Struct | ItemKind::Resource | ItemKind::NativeStruct
+        )
+    }
+}
+
+/// Declaration of a named entity, with everything required to show and to navigate to it.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub name: String,
+    pub fpath: FileId,
+    /// Range of the declared name.
+    pub name_range: Range,
+    /// Range of the whole declaration.
+    pub range: Range,
+    pub signature: String,
+    /// Contents of the `///` comments above the declaration.
+    pub docs: Option<String>,
+    /// Path of the enclosing module, like `0x1::Signer`.
+    pub container: Option<String>,
+    pub is_public: bool,
+    /// Fields of the struct.
+    pub fields: Vec<Item>,
+}
+
+impl Item {
+    pub fn new(
+        kind: ItemKind,
+        fpath: FileId,
+        source: &str,
+        node: Node,
+        container: Option<String>,
+    ) -> Option<Item> {
+        let name_node = node.child_by_field_name("name")?;
+        let name = node_text(source, name_node).to_string();
+        let mut fields = vec![];
+        let signature = match kind {
+            ItemKind::Struct | ItemKind::Resource | ItemKind::NativeStruct => {
+                let struct_path = container.as_ref().map(|path| format!("{}::{}", path, name));
+                fields = struct_fields(fpath, source, node, struct_path);
+                struct_signature(source, node)
+            }
+            ItemKind::Function | ItemKind::NativeFunction | ItemKind::SpecFunction => {
+                function_signature(source, node)
+            }
+            _ => String::new(),
+        };
+        Some(Item {
+            kind,
+            name,
+            fpath,
+            name_range: node_range(source, name_node),
+            range: node_range(source, node),
+            signature,
+            docs: doc_comments(source, node),
+            container,
+            is_public: has_child_token(node, "public"),
+            fields,
+        })
+    }
+
+    /// Local variable or function parameter, declared by `name_node`.
+    pub fn local(
+        kind: ItemKind,
+        fpath: FileId,
+        source: &str,
+        name: &str,
+        name_node: Node,
+        decl_node: Node,
+        signature: String,
+    ) -> Item {
+        Item {
+            kind,
+            name: name.to_string(),
+            fpath,
+            name_range: node_range(source, name_node),
+            range: node_range(source, decl_node),
+            signature,
+            docs: None,
+            container: None,
+            is_public: false,
+            fields: vec![],
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Item> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleDef {
+    /// Address as written in the source, sender address for modules outside of address blocks.
+    pub address: String,
+    pub name: String,
+    pub def: Item,
+    pub items: Vec<Item>,
+}
+
+impl ModuleDef {
+    pub fn path(&self) -> String {
+        format!("{}::{}", self.address, self.name)
+    }
+
+    pub fn is_at(&self, address: &str, name: &str) -> bool {
+        self.name == name && addresses_equal(&self.address, address)
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// Member named `name`, the kind of which satisfies `is_kind`.
+    pub fn member_of_kind(
+        &self,
+        name: &str,
+        is_kind: impl Fn(&ItemKind) -> bool,
+    ) -> Option<&Item> {
+        self.items
+            .iter()
+            .find(|item| item.name == name && is_kind(&item.kind))
+    }
+}
+
+pub fn addresses_equal(left: &str, right: &str) -> bool {
+    normalize_address(left) == normalize_address(right)
+}
+
+/// Address without the prefix and the leading zeros, equal for the same addresses written differently.
+pub fn normalize_address(address: &str) -> String {
+    address
+        .trim_start_matches("0x")
+        .trim_start_matches('0')
+        .to_lowercase()
+}
+
+pub fn file_modules(fpath: FileId, source_file: &SourceFile, sender: &str) -> Vec<ModuleDef> {
+    let source = source_file.source();
+    let mut modules = vec![];
+    for definition in source_file.definitions() {
+        match definition {
+            Definition::AddressBlock(address_block) => {
+                let address = match address_block.address() {
+                    Some(address) => address,
+                    None => continue,
+                };
+                for module in address_block.modules() {
+                    modules.extend(module_def(fpath, source, &module, address));
+                }
+            }
+            Definition::ModuleBlock(module) => {
+                modules.extend(module_def(fpath, source, &module, sender));
+            }
+            Definition::ScriptBlock(_) => {}
+        }
+    }
+    modules
+}
+
+pub fn module_def(
+    fpath: FileId,
+    source: &str,
+    module: &Module,
+    address: &str,
+) -> Option<ModuleDef> {
+    let name = module.name()?;
+    let path = format!("{}::{}", address, name);
+
+    let mut def = Item::new(ItemKind::Module, fpath, source, module.node, None)?;
+    def.signature = format!("module {}", path);
+    def.is_public = true;
+
+    let mut items = vec![];
+    for module_item in module.body().unwrap_or_default() {
+        let (kind, node) = match module_item {
+            ModuleItem::FuncDef(func) => (ItemKind::Function, func.node),
+            ModuleItem::NativeFuncDef(func) => (ItemKind::NativeFunction, func.node),
+            ModuleItem::Struct(struct_def) => {
+                let kind = if has_child_token(struct_def.node, "resource") {
+                    ItemKind::Resource
+                } else {
+                    ItemKind::Struct
+                };
+                (kind, struct_def.node)
+            }
+            ModuleItem::NativeStruct(struct_def) => (ItemKind::NativeStruct, struct_def.node),
+            ModuleItem::Spec(spec_block) => {
+                for spec_function in spec_functions(spec_block.node) {
+                    let spec_item = Item::new(
+                        ItemKind::SpecFunction,
+                        fpath,
+                        source,
+                        spec_function,
+                        Some(path.clone()),
+                    );
+                    items.extend(spec_item);
+                }
+                continue;
+            }
+            ModuleItem::Use(_) => continue,
+        };
+        items.extend(Item::new(kind, fpath, source, node, Some(path.clone())));
+    }
+    Some(ModuleDef {
+        address: address.to_string(),
+        name: name.to_string(),
+        def,
+        items,
+    })
+}
+
+/// `define` functions of the spec block.
+fn spec_functions(spec_block: Node) -> Vec<Node> {
+    let body = match spec_block.child_by_field_name("body") {
+        Some(body) => body,
+        None => return vec![],
+    };
+    let mut cursor = body.walk();
+    let spec_functions = body
+        .named_children(&mut cursor)
+        .filter(|node| matches!(node.kind(), "usual_spec_function" | "native_spec_function"))
+        .collect();
+    spec_functions
+}
+
+fn has_child_token(node: Node, token: &str) -> bool {
+    let mut cursor = node.walk();
+    let has_token = node
+        .children(&mut cursor)
+        .any(|child| child.kind() == token);
+    has_token
+}
+
+fn function_signature(source: &str, node: Node) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .map(|body| body.start_byte())
+        .unwrap_or_else(|| node.end_byte());
+    single_line(source[node.start_byte()..end].trim_end_matches(';'))
+}
+
+fn struct_fields(
+    fpath: FileId,
+    source: &str,
+    node: Node,
+    struct_path: Option<String>,
+) -> Vec<Item> {
+    let fields_node = match node.child_by_field_name("fields") {
+        Some(fields_node) => fields_node,
+        None => return vec![],
+    };
+    let mut cursor = fields_node.walk();
+    let fields = fields_node
+        .named_children(&mut cursor)
+        .filter(|field| field.kind() == "field_annotation")
+        .filter_map(|field| {
+            let name_node = field.child_by_field_name("field")?;
+            Some(Item {
+                kind: ItemKind::Field,
+                name: node_text(source, name_node).to_string(),
+                fpath,
+                name_range: node_range(source, name_node),
+                range: node_range(source, field),
+                signature: single_line(node_text(source, field)),
+                docs: doc_comments(source, field),
+                container: struct_path.clone(),
+                is_public: false,
+                fields: vec![],
+            })
+        })
+        .collect();
+    fields
+}
+
+fn struct_signature(source: &str, node: Node) -> String {
+    let fields_node = match node.child_by_field_name("fields") {
+        Some(fields_node) => fields_node,
+        None => return single_line(node_text(source, node).trim_end_matches(';')),
+    };
+    let header = single_line(&source[node.start_byte()..fields_node.start_byte()]);
+
+    let mut cursor = fields_node.walk();
+    let fields = fields_node
+        .named_children(&mut cursor)
+        .filter(|field| field.kind() == "field_annotation")
+        .map(|field| format!("    {},", single_line(node_text(source, field))))
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, fields.join("\n"))
+    }
+}
+
+/// Consecutive `///` comments right above the node.
+pub fn doc_comments(source: &str, node: Node) -> Option<String> {
+    let mut lines = vec![];
+    let mut current = node;
+    while let Some(prev) = current.prev_named_sibling() {
+        if prev.kind() != "line_comment" {
+            break;
+        }
+        let comment = node_text(source, prev);
+        if !comment.starts_with("///") {
+            break;
+        }
+        let line = comment.trim_start_matches("///");
+        lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end());
+        current = prev;
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(lines.join("\n"))
+}
+
+/// All modules declared in the tracked files, stdlib and modules folders.
+#[derive(Debug, Default)]
+pub struct ItemIndex {
+    modules: Vec<ModuleDef>,
+}
+
+impl ItemIndex {
+    /// Modules of the tracked files come first, so that they shadow the stdlib and modules
+    /// folders ones declared at the same address.
+    pub fn new(db: &RootDatabase) -> ItemIndex {
+        let tracked_files: HashSet<_> = db.tracked_files().into_iter().collect();
+        let mut fpaths = db.all_fpaths();
+        fpaths.sort_by_key(|fpath| !tracked_files.contains(fpath));
+
+        let mut modules = vec![];
+        for fpath in fpaths {
+            modules.extend(db.file_modules(fpath).iter().cloned());
+        }
+        ItemIndex { modules }
+    }
+
+    pub fn modules(&self) -> &[ModuleDef] {
+        &self.modules
+    }
+
+    /// Module at the address, the one of the tracked file if there are several.
+    pub fn module(&self, address: &str, name: &str) -> Option<&ModuleDef> {
+        self.modules
+            .iter()
+            .find(|module| module.is_at(address, name))
+    }
+}
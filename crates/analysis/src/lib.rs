@@ -1,6 +1,19 @@
 pub mod analysis;
 pub mod change;
+pub mod compiler_diagnostics;
 pub mod completion;
 pub mod config;
 pub mod db;
+pub mod dependencies;
+pub mod document_symbols;
+pub mod goto_definition;
+pub mod hover;
+pub mod infer;
+pub mod items;
+pub mod query;
+pub mod references;
+pub mod rename;
+pub mod resolve;
+pub mod syntax_diagnostics;
 pub mod utils;
+pub mod workspace_symbols;
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::db::RootDatabase;
+use utils::FileId;
+
+/// Revision of the database inputs, incremented by every `apply_change`.
+pub type Revision = u64;
+
+/// Input of the database, changed only by `apply_change`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Input {
+    /// Config, along with the stdlib and modules folders files loaded with it.
+    Config,
+    /// Set of the tracked files.
+    FileSet,
+    /// Text of the tracked file.
+    File(FileId),
+}
+
+#[derive(Debug)]
+struct Memo<V> {
+    value: V,
+    /// Inputs read while computing the value, including the ones of the nested queries.
+    inputs: Vec<Input>,
+    verified_at: Revision,
+}
+
+/// Memoized values of the query, shared between snapshots of the database.
+/// Value is computed again only if some of the inputs it was computed from has changed.
+#[derive(Debug)]
+pub struct QueryTable<K: Eq + Hash, V> {
+    memos: Mutex<HashMap<K, Memo<V>>>,
+}
+
+impl<K: Eq + Hash, V> Default for QueryTable<K, V> {
+    fn default() -> Self {
+        QueryTable {
+            memos: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> QueryTable<K, V> {
+    pub fn get(
+        &self,
+        db: &RootDatabase,
+        key: K,
+        compute: impl FnOnce(&RootDatabase, K) -> V,
+    ) -> V {
+        let revision = db.revision();
+        if let Some(memo) = self.memos.lock().unwrap().get_mut(&key) {
+            // memo of the newer snapshot might be computed from the inputs this one doesn't have
+            let is_valid = memo.verified_at <= revision
+                && memo
+                    .inputs
+                    .iter()
+                    .all(|input| db.changed_at(*input) <= memo.verified_at);
+            if is_valid {
+                memo.verified_at = revision;
+                db.report_inputs(&memo.inputs);
+                return memo.value.clone();
+            }
+        }
+
+        let (value, inputs) = db.track_inputs(|| compute(db, key.clone()));
+        let mut memos = self.memos.lock().unwrap();
+        let has_newer_memo = matches!(memos.get(&key), Some(memo) if memo.verified_at > revision);
+        if !has_newer_memo {
+            memos.insert(
+                key,
+                Memo {
+                    value: value.clone(),
+                    inputs: inputs.clone(),
+                    verified_at: revision,
+                },
+            );
+        }
+        db.report_inputs(&inputs);
+        value
+    }
+
+    /// Drops the memoized value, so that values of the removed inputs are not kept forever.
+    pub fn evict(&self, key: &K) {
+        self.memos.lock().unwrap().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change::AnalysisChange;
+    use crate::config::Config;
+    use std::cell::Cell;
+    use utils::FilesSourceText;
+
+    fn text_len(
+        db: &RootDatabase,
+        table: &QueryTable<FileId, usize>,
+        calls: &Cell<usize>,
+        fpath: FileId,
+    ) -> usize {
+        table.get(db, fpath, |db, fpath| {
+            calls.set(calls.get() + 1);
+            db.file_text(fpath).map_or(0, |text| text.len())
+        })
+    }
+
+    #[test]
+    fn value_is_recomputed_only_after_its_input_changes() {
+        let (a, b) = (FileId::new("/a.move"), FileId::new("/b.move"));
+        let mut files = FilesSourceText::new();
+        files.insert(a, "script {}".to_string());
+        files.insert(b, "module M {}".to_string());
+        let mut db = RootDatabase::new(Config::default(), files);
+        let table = QueryTable::default();
+        let calls = Cell::new(0);
+
+        assert_eq!(text_len(&db, &table, &calls, a), 9);
+        assert_eq!(text_len(&db, &table, &calls, a), 9);
+        assert_eq!(calls.get(), 1);
+
+        let mut change = AnalysisChange::new();
+        change.update_file(b, "module Mod {}".to_string());
+        db.apply_change(change);
+        assert_eq!(text_len(&db, &table, &calls, a), 9);
+        assert_eq!(calls.get(), 1);
+
+        let mut change = AnalysisChange::new();
+        change.update_file(a, "script { }".to_string());
+        db.apply_change(change);
+        assert_eq!(text_len(&db, &table, &calls, a), 10);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn older_snapshot_does_not_use_newer_values() {
+        let a = FileId::new("/a.move");
+        let mut files = FilesSourceText::new();
+        files.insert(a, "script {}".to_string());
+        let mut db = RootDatabase::new(Config::default(), files);
+        let snapshot = db.clone();
+        let table = QueryTable::default();
+        let calls = Cell::new(0);
+
+        let mut change = AnalysisChange::new();
+        change.update_file(a, "script { }".to_string());
+        db.apply_change(change);
+        assert_eq!(text_len(&db, &table, &calls, a), 10);
+        assert_eq!(text_len(&snapshot, &table, &calls, a), 9);
+        assert_eq!(text_len(&db, &table, &calls, a), 10);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn evicted_value_is_recomputed() {
+        let a = FileId::new("/a.move");
+        let mut files = FilesSourceText::new();
+        files.insert(a, "script {}".to_string());
+        let db = RootDatabase::new(Config::default(), files);
+        let table = QueryTable::default();
+        let calls = Cell::new(0);
+
+        assert_eq!(text_len(&db, &table, &calls, a), 9);
+        table.evict(&a);
+        assert!(table.memos.lock().unwrap().is_empty());
+        assert_eq!(text_len(&db, &table, &calls, a), 9);
+        assert_eq!(calls.get(), 2);
+    }
+}
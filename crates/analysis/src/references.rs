@@ -0,0 +1,121 @@
+use lsp_types::Range;
+use syntax::ast::{SourceFile, UseDecl};
+
+use crate::db::{FilePosition, FileRange, RootDatabase};
+use crate::items::{Item, ItemIndex, ItemKind};
+use crate::resolve::{resolve_position, Resolver};
+use crate::utils::tree::{descendants, is_ident, node_range, node_text};
+use utils::FileId;
+
+/// All usages of the item under the cursor, across every known file.
+pub fn find_references(
+    db: &RootDatabase,
+    position: FilePosition,
+    include_declaration: bool,
+) -> Option<Vec<FileRange>> {
+    let index = db.item_index();
+    let (item, _) = resolve_position(db, &index, &position)?;
+
+    let mut references = vec![];
+    for fpath in search_files(db, &item, position.fpath) {
+        let source_file = match db.any_source_file(fpath) {
+            Some(source_file) => source_file,
+            None => continue,
+        };
+        let ranges = references_in_file(db, &index, &item, fpath, &source_file);
+        references.extend(ranges.into_iter().map(|range| FileRange { fpath, range }));
+    }
+
+    let declaration = FileRange {
+        fpath: item.fpath,
+        range: item.name_range,
+    };
+    references.retain(|reference| reference != &declaration);
+    if include_declaration {
+        references.push(declaration);
+    }
+    references.sort_by_key(|reference| {
+        (
+            reference.fpath,
+            reference.range.start.line,
+            reference.range.start.character,
+        )
+    });
+    Some(references)
+}
+
+/// Usages of the item under the cursor in the current file, declaration included.
+pub fn highlight_references(db: &RootDatabase, position: FilePosition) -> Option<Vec<Range>> {
+    let index = db.item_index();
+    let (item, _) = resolve_position(db, &index, &position)?;
+
+    let source_file = db.any_source_file(position.fpath)?;
+    let mut ranges = references_in_file(db, &index, &item, position.fpath, &source_file);
+    if item.fpath == position.fpath && !ranges.contains(&item.name_range) {
+        ranges.insert(0, item.name_range);
+    }
+    Some(ranges)
+}
+
+/// Files, which could contain usages of the item, referred from the file `fpath`.
+pub fn search_files(db: &RootDatabase, item: &Item, fpath: FileId) -> Vec<FileId> {
+    match item.kind {
+        // locals are not visible outside of the declaring file
+        ItemKind::Local | ItemKind::Parameter | ItemKind::TypeParameter => vec![fpath],
+        _ => db.all_fpaths(),
+    }
+}
+
+fn references_in_file(
+    db: &RootDatabase,
+    index: &ItemIndex,
+    item: &Item,
+    fpath: FileId,
+    source_file: &SourceFile,
+) -> Vec<Range> {
+    let source = source_file.source();
+    let names = candidate_names(source_file, &item.name);
+
+    let resolver = Resolver::new(
+        index,
+        fpath,
+        source,
+        &db.config().sender().normalized_original,
+    );
+    descendants(source_file.tree.root_node())
+        .into_iter()
+        .filter(is_ident)
+        .filter(|ident| names.iter().any(|name| name == node_text(source, *ident)))
+        .filter(|ident| {
+            is_same_item(resolver.resolve(*ident), item)
+                || is_same_item(resolver.resolve_shorthand_local(*ident), item)
+        })
+        .map(|ident| node_range(source, ident))
+        .collect()
+}
+
+/// Name of the item, and all the `use` aliases the file gives to anything named like that.
+fn candidate_names(source_file: &SourceFile, name: &str) -> Vec<String> {
+    let mut names = vec![name.to_string()];
+    let use_decls = descendants(source_file.tree.root_node())
+        .into_iter()
+        .filter(|node| node.kind() == "use_decl")
+        .map(|node| UseDecl::new(source_file.source(), node));
+    for use_decl in use_decls {
+        if use_decl.module() == Some(name) {
+            names.extend(use_decl.alias().map(str::to_string));
+        }
+        for use_member in use_decl.members() {
+            if use_member.member() == Some(name) {
+                names.extend(use_member.alias().map(str::to_string));
+            }
+        }
+    }
+    names
+}
+
+pub fn is_same_item(resolved: Option<Item>, item: &Item) -> bool {
+    resolved
+        .map(|resolved| resolved.fpath == item.fpath && resolved.name_range == item.name_range)
+        .unwrap_or(false)
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use lsp_types::{Range, TextEdit};
+use tree_sitter::Node;
+
+use crate::db::{FilePosition, RootDatabase};
+use crate::items::{Item, ItemIndex, ItemKind};
+use crate::references::{is_same_item, search_files};
+use crate::resolve::{resolve_position, Resolver};
+use crate::utils::tree::{descendants, is_ident, node_range, node_text};
+use utils::FileId;
+
+/// Keywords and builtin type names, which cannot be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "abort", "acquires", "address", "as", "break", "continue", "copy", "copyable", "else",
+    "false", "fun", "if", "let", "loop", "module", "move", "mut", "native", "public", "resource",
+    "return", "script", "spec", "struct", "true", "use", "while", "Self", "bool", "signer", "u8",
+    "u64", "u128", "vector",
+];
+
+pub type FileEdits = HashMap<FileId, Vec<TextEdit>>;
+
+/// Range of the identifier under the cursor, if the item it refers to could be renamed.
+pub fn prepare_rename(db: &RootDatabase, position: FilePosition) -> Option<Range> {
+    let index = db.item_index();
+    let (item, ident_range) = resolve_position(db, &index, &position)?;
+    if is_stdlib_item(db, &item) {
+        return None;
+    }
+    Some(ident_range)
+}
+
+/// Edits, required to rename the item under the cursor and all of its usages to `new_name`.
+pub fn rename(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+) -> Result<FileEdits, String> {
+    if !is_valid_ident(new_name) {
+        return Err(format!("'{}' is not a valid identifier", new_name));
+    }
+    let index = db.item_index();
+    let (item, _) = resolve_position(db, &index, &position)
+        .ok_or_else(|| "No item to rename at the cursor".to_string())?;
+    if is_stdlib_item(db, &item) {
+        return Err(format!(
+            "Cannot rename '{}' declared in the stdlib",
+            item.name
+        ));
+    }
+    if item.name == new_name {
+        return Ok(FileEdits::new());
+    }
+    if item.kind == ItemKind::Field && struct_has_field(&index, &item, new_name) {
+        return Err(format!("Struct already has a field named '{}'", new_name));
+    }
+
+    let sender = &db.config().sender().normalized_original;
+    let mut edits = FileEdits::new();
+    for fpath in search_files(db, &item, position.fpath) {
+        let source_file = match db.any_source_file(fpath) {
+            Some(source_file) => source_file,
+            None => continue,
+        };
+        let source = source_file.source();
+        let resolver = Resolver::new(&index, fpath, source, sender);
+
+        let mut file_edits = vec![];
+        for ident in descendants(source_file.tree.root_node()) {
+            // aliased usages keep their aliases
+            if !is_ident(&ident) || node_text(source, ident) != item.name {
+                continue;
+            }
+            let new_text = if is_same_item(resolver.resolve(ident), &item) {
+                if item.kind == ItemKind::Field && is_shorthand(ident) {
+                    format!("{}: {}", new_name, item.name)
+                } else {
+                    new_name.to_string()
+                }
+            } else if is_same_item(resolver.resolve_shorthand_local(ident), &item) {
+                format!("{}: {}", item.name, new_name)
+            } else {
+                continue;
+            };
+            if let Some(existing) = resolver.resolve_name(ident, item.kind, new_name) {
+                return Err(clash_error(new_name, &existing));
+            }
+            file_edits.push(TextEdit::new(node_range(source, ident), new_text));
+        }
+        if !file_edits.is_empty() {
+            edits.insert(fpath, file_edits);
+        }
+    }
+    Ok(edits)
+}
+
+fn is_valid_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts_correctly = chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false);
+    starts_correctly
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+fn is_stdlib_item(db: &RootDatabase, item: &Item) -> bool {
+    db.is_stdlib_file(item.fpath)
+}
+
+/// `age` in `T { age }` and `let T { age } = t;`.
+fn is_shorthand(ident: Node) -> bool {
+    match ident.parent() {
+        Some(parent) if parent.kind() == "exp_field" => {
+            parent.child_by_field_name("exp").is_none()
+        }
+        Some(parent) if parent.kind() == "bind_field" => {
+            parent.child_by_field_name("bind").is_none()
+        }
+        _ => false,
+    }
+}
+
+fn struct_has_field(index: &ItemIndex, field: &Item, name: &str) -> bool {
+    let struct_item = index
+        .modules()
+        .iter()
+        .flat_map(|module| module.items.iter())
+        .find(|item| {
+            item.fields
+                .iter()
+                .any(|f| f.fpath == field.fpath && f.name_range == field.name_range)
+        });
+    struct_item
+        .map(|struct_item| struct_item.field(name).is_some())
+        .unwrap_or(false)
+}
+
+fn clash_error(new_name: &str, existing: &Item) -> String {
+    format!(
+        "Renaming to '{}' clashes with the existing {}",
+        new_name,
+        existing.signature.lines().next().unwrap_or(new_name)
+    )
+}
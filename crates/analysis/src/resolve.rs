@@ -0,0 +1,463 @@
+use lsp_types::Range;
+use syntax::ast::UseDecl;
+use tree_sitter::Node;
+
+use crate::db::{FilePosition, RootDatabase};
+use crate::items::{Item, ItemIndex, ItemKind, ModuleDef};
+use crate::utils::tree::{ancestors, ident_at_position, node_range, node_text, single_line};
+use utils::FileId;
+
+/// Declaration of the identifier at the position.
+pub fn resolve(db: &RootDatabase, position: &FilePosition) -> Option<Item> {
+    let index = db.item_index();
+    resolve_position(db, &index, position).map(|(item, _)| item)
+}
+
+/// Declaration of the identifier at the position, along with the range of the identifier.
+pub fn resolve_position(
+    db: &RootDatabase,
+    index: &ItemIndex,
+    position: &FilePosition,
+) -> Option<(Item, Range)> {
+    let source_file = db.any_source_file(position.fpath)?;
+    let ident = ident_at_position(&source_file, position.pos)?;
+
+    let resolver = Resolver::new(
+        index,
+        position.fpath,
+        source_file.source(),
+        &db.config().sender().normalized_original,
+    );
+    let item = resolver.resolve(ident)?;
+    Some((item, node_range(source_file.source(), ident)))
+}
+
+/// Resolves identifiers of a single file into their declarations.
+pub struct Resolver<'a> {
+    index: &'a ItemIndex,
+    fpath: FileId,
+    source: &'a str,
+    sender: &'a str,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(
+        index: &'a ItemIndex,
+        fpath: FileId,
+        source: &'a str,
+        sender: &'a str,
+    ) -> Resolver<'a> {
+        Resolver {
+            index,
+            fpath,
+            source,
+            sender,
+        }
+    }
+
+    pub fn fpath(&self) -> FileId {
+        self.fpath
+    }
+
+    /// Declaration of the identifier `ident`. Identifier of a declaration resolves to itself.
+    pub fn resolve(&self, ident: Node<'a>) -> Option<Item> {
+        let parent = ident.parent()?;
+        let name = self.text(ident);
+        match (parent.kind(), ident.kind()) {
+            ("module_definition", _) => {
+                self.current_module(ident).map(|module| module.def.clone())
+            }
+            ("usual_function_definition", _)
+            | ("native_function_definition", _)
+            | ("struct_definition", _)
+            | ("native_struct_definition", _)
+            | ("usual_spec_function", _)
+            | ("native_spec_function", _) => self.current_module(ident)?.member(name).cloned(),
+            ("use_decl", "module_identifier") => {
+                let use_decl = UseDecl::new(self.source, parent);
+                self.index
+                    .module(use_decl.address()?, use_decl.module()?)
+                    .map(|module| module.def.clone())
+            }
+            ("use_member", _) => {
+                let use_decl = UseDecl::new(self.source, parent.parent()?);
+                let member = parent.child_by_field_name("member")?;
+                self.index
+                    .module(use_decl.address()?, use_decl.module()?)?
+                    .member(self.text(member))
+                    .cloned()
+            }
+            ("module_access", "module_identifier") => self
+                .resolve_module_ref(parent, name)
+                .map(|module| module.def.clone()),
+            ("module_access", "identifier") => self.resolve_module_access(parent, name),
+            ("field_annotation", _) => {
+                let struct_node =
+                    ancestors(parent).find(|node| node.kind() == "struct_definition")?;
+                let struct_name = self.text(struct_node.child_by_field_name("name")?);
+                let struct_item = self.current_module(struct_node)?.member(struct_name)?;
+                struct_item.field(name).cloned()
+            }
+            ("exp_field", _) => {
+                let pack_expr =
+                    ancestors(parent).find(|node| node.kind() == "pack_expression")?;
+                let module_access = pack_expr.named_child(0)?.named_child(0)?;
+                self.resolve_struct_ref(module_access)?.field(name).cloned()
+            }
+            ("bind_field", "field_identifier") => {
+                let bind_unpack = ancestors(parent).find(|node| node.kind() == "bind_unpack")?;
+                self.resolve_struct_ref(bind_unpack.named_child(0)?)?
+                    .field(name)
+                    .cloned()
+            }
+            (_, "bind_var") => self.resolve_local(ident, name),
+            ("function_parameter", "variable_identifier") => self.resolve_local(ident, name),
+            ("type_parameter", _) => self.resolve_type_parameter(ident, name),
+            ("move_or_copy_expression", "variable_identifier") => self.resolve_local(ident, name),
+            _ => None,
+        }
+    }
+
+    /// Local variable, referred by the field shorthand, like `age` in `T { age }`.
+    pub fn resolve_shorthand_local(&self, ident: Node<'a>) -> Option<Item> {
+        let parent = ident.parent()?;
+        let value_field = match parent.kind() {
+            "exp_field" => "exp",
+            "bind_field" => "bind",
+            _ => return None,
+        };
+        if parent.child_by_field_name(value_field).is_some() {
+            return None;
+        }
+        self.resolve_local(ident, self.text(ident))
+    }
+
+    /// Item of the same kind as `kind`, named `name` and visible from the identifier.
+    pub fn resolve_name(&self, ident: Node<'a>, kind: ItemKind, name: &str) -> Option<Item> {
+        match kind {
+            ItemKind::Local | ItemKind::Parameter => self.resolve_local(ident, name),
+            ItemKind::TypeParameter => self.resolve_type_parameter(ident, name),
+            ItemKind::Module => {
+                let parent = ident.parent()?;
+                if parent.kind() == "module_access" {
+                    return self
+                        .resolve_module_ref(parent, name)
+                        .map(|module| module.def.clone());
+                }
+                if parent.kind() == "use_decl" {
+                    let use_decl = UseDecl::new(self.source, parent);
+                    return self
+                        .index
+                        .module(use_decl.address()?, name)
+                        .map(|module| module.def.clone());
+                }
+                let module = self.current_module(ident)?;
+                self.index
+                    .module(&module.address, name)
+                    .map(|module| module.def.clone())
+            }
+            ItemKind::Field => None,
+            _ => {
+                // spec functions are visible only to the other spec functions
+                let is_kind = |other: &ItemKind| {
+                    kind == ItemKind::SpecFunction || *other != ItemKind::SpecFunction
+                };
+                let parent = ident.parent()?;
+                if parent.kind() == "module_access" {
+                    if let Some(module_ident) = parent.child_by_field_name("module") {
+                        let module = self.resolve_module_ref(parent, self.text(module_ident))?;
+                        return module.member_of_kind(name, is_kind).cloned();
+                    }
+                }
+                self.resolve_module_member(ident, name, is_kind)
+            }
+        }
+    }
+
+    fn resolve_struct_ref(&self, module_access: Node<'a>) -> Option<Item> {
+        if module_access.kind() != "module_access" {
+            return None;
+        }
+        let name_node =
+            module_access.named_child(module_access.named_child_count().checked_sub(1)?)?;
+        self.resolve_module_access(module_access, self.text(name_node))
+            .filter(|item| item.kind.is_struct())
+    }
+
+    fn resolve_module_access(&self, module_access: Node<'a>, name: &str) -> Option<Item> {
+        let is_inside_spec = ancestors(module_access).any(|node| node.kind() == "spec_block");
+        if let Some(module_ident) = module_access.child_by_field_name("module") {
+            let module = self.resolve_module_ref(module_access, self.text(module_ident))?;
+            return module
+                .member_of_kind(name, |kind| {
+                    is_inside_spec || *kind != ItemKind::SpecFunction
+                })
+                .cloned();
+        }
+        let context = module_access.parent()?;
+        let is_kind: fn(&ItemKind) -> bool = match context.kind() {
+            "name_expression" => {
+                let expr_kind = context.parent().map(|expr| expr.kind());
+                match expr_kind {
+                    Some("call_expression") if is_inside_spec => ItemKind::is_spec_callable,
+                    Some("call_expression") => ItemKind::is_function,
+                    Some("pack_expression") => ItemKind::is_struct,
+                    _ => return self.resolve_local(module_access, name),
+                }
+            }
+            "apply_type" => {
+                // type parameters shadow the structs of the module
+                if let Some(item) = self.resolve_type_parameter(module_access, name) {
+                    return Some(item);
+                }
+                ItemKind::is_struct
+            }
+            "bind_unpack" | "resource_accquires" => ItemKind::is_struct,
+            _ => return None,
+        };
+        self.resolve_module_member(module_access, name, is_kind)
+    }
+
+    /// Function or struct, declared in the current module or imported with `use`.
+    fn resolve_module_member(
+        &self,
+        node: Node<'a>,
+        name: &str,
+        is_kind: impl Fn(&ItemKind) -> bool,
+    ) -> Option<Item> {
+        if let Some(item) = self
+            .current_module(node)
+            .and_then(|module| module.member_of_kind(name, &is_kind))
+        {
+            return Some(item.clone());
+        }
+        for use_decl in self.use_decls(node) {
+            for use_member in use_decl.members() {
+                let member = match use_member.member() {
+                    Some(member) => member,
+                    None => continue,
+                };
+                if use_member.alias().unwrap_or(member) != name {
+                    continue;
+                }
+                return self
+                    .index
+                    .module(use_decl.address()?, use_decl.module()?)?
+                    .member_of_kind(member, &is_kind)
+                    .cloned();
+            }
+        }
+        None
+    }
+
+    /// Module, referred to by `name` in the `module_access` node: `Self`, an alias from `use`
+    /// or a module at the explicit address.
+    pub fn resolve_module_ref(
+        &self,
+        module_access: Node<'a>,
+        name: &str,
+    ) -> Option<&'a ModuleDef> {
+        if let Some(address) = module_access.child_by_field_name("address") {
+            return self.index.module(self.text(address), name);
+        }
+        if name == "Self" {
+            return self.current_module(module_access);
+        }
+        for use_decl in self.use_decls(module_access) {
+            if !use_decl.members().is_empty() {
+                continue;
+            }
+            let module = match use_decl.module() {
+                Some(module) => module,
+                None => continue,
+            };
+            if use_decl.alias().unwrap_or(module) == name {
+                return self.index.module(use_decl.address()?, module);
+            }
+        }
+        None
+    }
+
+    /// Local variable or function parameter, visible from the node.
+    fn resolve_local(&self, node: Node<'a>, name: &str) -> Option<Item> {
+        let mut child = node;
+        for ancestor in ancestors(node) {
+            match ancestor.kind() {
+                "block" => {
+                    let mut cursor = ancestor.walk();
+                    let preceding_lets = ancestor
+                        .named_children(&mut cursor)
+                        .filter(|item| item.kind() == "let_statement")
+                        .filter(|item| item.end_byte() <= child.start_byte())
+                        .collect::<Vec<_>>();
+                    for let_statement in preceding_lets.into_iter().rev() {
+                        if let Some(item) = self.let_binding(let_statement, name) {
+                            return Some(item);
+                        }
+                    }
+                }
+                "let_statement" if child.kind() != "bind_list" => {}
+                "let_statement" => return self.let_binding(ancestor, name),
+                "lambda_expression" => {
+                    let bindings = ancestor.child_by_field_name("bindings")?;
+                    let mut binders = vec![];
+                    collect_binders(bindings, &mut binders);
+                    if let Some(binder) = binders.into_iter().find(|b| self.text(*b) == name) {
+                        return Some(Item::local(
+                            ItemKind::Local,
+                            self.fpath,
+                            self.source,
+                            name,
+                            binder,
+                            bindings,
+                            format!("|{}|", name),
+                        ));
+                    }
+                }
+                "usual_function_definition"
+                | "native_function_definition"
+                | "usual_spec_function"
+                | "native_spec_function" => {
+                    return self.function_parameter(ancestor, name);
+                }
+                _ => {}
+            }
+            child = ancestor;
+        }
+        None
+    }
+
+    fn let_binding(&self, let_statement: Node<'a>, name: &str) -> Option<Item> {
+        let mut binders = vec![];
+        collect_binders(let_statement.child_by_field_name("binds")?, &mut binders);
+        let binder = binders
+            .into_iter()
+            .rev()
+            .find(|binder| self.text(*binder) == name)?;
+
+        let binds = self.text(let_statement.child_by_field_name("binds")?);
+        let signature = match let_statement.child_by_field_name("type") {
+            Some(typ) if binder.kind() == "bind_var" && binds == name => {
+                format!("let {}: {}", name, single_line(self.text(typ)))
+            }
+            _ => format!("let {}", name),
+        };
+        Some(Item::local(
+            ItemKind::Local,
+            self.fpath,
+            self.source,
+            name,
+            binder,
+            let_statement,
+            signature,
+        ))
+    }
+
+    fn function_parameter(&self, function: Node<'a>, name: &str) -> Option<Item> {
+        let params = function.child_by_field_name("params")?;
+        let mut cursor = params.walk();
+        let param = params
+            .named_children(&mut cursor)
+            .filter(|param| param.kind() == "function_parameter")
+            .find(|param| {
+                param
+                    .child_by_field_name("name")
+                    .map(|param_name| self.text(param_name) == name)
+                    .unwrap_or(false)
+            })?;
+        Some(Item::local(
+            ItemKind::Parameter,
+            self.fpath,
+            self.source,
+            name,
+            param.child_by_field_name("name")?,
+            param,
+            single_line(self.text(param)),
+        ))
+    }
+
+    /// Type parameter of the enclosing function or struct.
+    fn resolve_type_parameter(&self, node: Node<'a>, name: &str) -> Option<Item> {
+        let generic = ancestors(node).find(|node| {
+            matches!(
+                node.kind(),
+                "usual_function_definition"
+                    | "native_function_definition"
+                    | "usual_spec_function"
+                    | "native_spec_function"
+                    | "struct_definition"
+                    | "native_struct_definition"
+            )
+        })?;
+        let type_params = generic.child_by_field_name("type_parameters")?;
+        let mut cursor = type_params.walk();
+        let type_param = type_params.named_children(&mut cursor).find(|type_param| {
+            type_param
+                .named_child(0)
+                .map(|param_name| self.text(param_name) == name)
+                .unwrap_or(false)
+        })?;
+        Some(Item::local(
+            ItemKind::TypeParameter,
+            self.fpath,
+            self.source,
+            name,
+            type_param.named_child(0)?,
+            type_param,
+            single_line(self.text(type_param)),
+        ))
+    }
+
+    /// Module, which contains the node.
+    pub fn current_module(&self, node: Node<'a>) -> Option<&'a ModuleDef> {
+        let module_node = ancestors(node).find(|node| node.kind() == "module_definition")?;
+        let name = self.text(module_node.child_by_field_name("name")?);
+        let address = module_node
+            .parent()
+            .filter(|parent| parent.kind() == "address_block")
+            .and_then(|address_block| address_block.child_by_field_name("address"))
+            .map(|address| self.text(address))
+            .unwrap_or(self.sender);
+        self.index.module(address, name)
+    }
+
+    /// `use` declarations visible from the node.
+    fn use_decls(&self, node: Node<'a>) -> Vec<UseDecl<'a>> {
+        let scope = ancestors(node)
+            .find(|node| matches!(node.kind(), "module_body" | "script_block" | "source_file"));
+        let scope = match scope {
+            Some(scope) => scope,
+            None => return vec![],
+        };
+        let mut cursor = scope.walk();
+        let use_decls = scope
+            .named_children(&mut cursor)
+            .filter(|node| node.kind() == "use_decl")
+            .map(|node| UseDecl::new(self.source, node))
+            .collect();
+        use_decls
+    }
+
+    fn text(&self, node: Node) -> &'a str {
+        node_text(self.source, node)
+    }
+}
+
+/// Nodes, which introduce new local variables in the bind list.
+fn collect_binders<'t>(node: Node<'t>, binders: &mut Vec<Node<'t>>) {
+    match node.kind() {
+        "bind_var" => binders.push(node),
+        "bind_field" => match node.child_by_field_name("bind") {
+            Some(bind) => collect_binders(bind, binders),
+            // `T { field }` binds variable `field`
+            None => binders.extend(node.child_by_field_name("field")),
+        },
+        _ => {
+            let mut cursor = node.walk();
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            for child in children {
+                collect_binders(child, binders);
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use syntax::ast::SourceFile;
+use tree_sitter::Node;
+
+use crate::db::FileDiagnostic;
+use crate::utils::tree::{node_range, node_text};
+use utils::FileId;
+
+/// Syntax errors found by the tree-sitter parser. Unlike the compiler, the parser recovers
+/// from errors, so every broken part of the file is reported.
+pub fn syntax_diagnostics(fpath: FileId, source_file: &SourceFile) -> FileDiagnostic {
+    let source = source_file.source();
+
+    let mut diagnostics = vec![];
+    let mut cursor = source_file.tree.root_node().walk();
+    loop {
+        let node = cursor.node();
+        // nodes inside of the ERROR are leftovers of the same error
+        let is_error = node.is_error() || node.is_missing();
+        if is_error {
+            diagnostics.push(syntax_error(source, node));
+        }
+        if (!is_error && node.has_error() && cursor.goto_first_child())
+            || cursor.goto_next_sibling()
+        {
+            continue;
+        }
+        loop {
+            if !cursor.goto_parent() {
+                return FileDiagnostic { fpath, diagnostics };
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+}
+
+fn syntax_error(source: &str, node: Node) -> Diagnostic {
+    let (range, message) = if node.is_missing() {
+        (
+            node_range(source, node),
+            format!("Missing '{}'", node.kind()),
+        )
+    } else {
+        // point at the first word of the unparsed text, like the compiler does
+        let token = node_text(source, node)
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let start = node_range(source, node).start;
+        let token_len = token.encode_utf16().count() as u64;
+        let end = Position::new(start.line, start.character + token_len);
+        (Range::new(start, end), format!("Unexpected '{}'", token))
+    };
+    Diagnostic::new(
+        range,
+        Some(DiagnosticSeverity::Error),
+        None,
+        None,
+        message,
+        None,
+        None,
+    )
+}
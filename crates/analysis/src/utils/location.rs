@@ -61,6 +61,30 @@ where
         ))
     }
 
+    /// Byte index of the `position`, columns past the end of the line point to the line end.
+    /// Columns are counted in UTF-16 code units, as LSP positions are.
+    pub fn byte_index(&self, position: Position) -> Option<usize> {
+        let line_index = LineIndex::from(position.line as u32);
+        let line_start = self.line_start(line_index).ok()?.to_usize();
+        let line_end = self
+            .line_starts
+            .get(position.line as usize + 1)
+            .map(|index| index.to_usize())
+            .unwrap_or_else(|| self.source.as_ref().len());
+        let line = self.source.as_ref()[line_start..line_end].trim_end_matches(&['\n', '\r'][..]);
+        let mut utf16_column = 0;
+        let column = line
+            .char_indices()
+            .find(|(_, c)| {
+                let is_reached = utf16_column >= position.character as usize;
+                utf16_column += c.len_utf16();
+                is_reached
+            })
+            .map(|(index, _)| index)
+            .unwrap_or_else(|| line.len());
+        Some(line_start + column)
+    }
+
     fn line_start(&self, line_index: LineIndex) -> Result<ByteIndex, LineIndexOutOfBoundsError> {
         use std::cmp::Ordering;
 
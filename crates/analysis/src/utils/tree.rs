@@ -0,0 +1,161 @@
+use lsp_types::{Position, Range};
+use syntax::ast::SourceFile;
+use tree_sitter::{Node, Point};
+
+const IDENT_KINDS: &[&str] = &[
+    "identifier",
+    "module_identifier",
+    "struct_identifier",
+    "function_identifier",
+    "variable_identifier",
+    "field_identifier",
+    "type_parameter_identifier",
+    "bind_var",
+];
+
+pub fn is_ident(node: &Node) -> bool {
+    IDENT_KINDS.contains(&node.kind())
+}
+
+/// LSP position of the tree-sitter point. Points count columns in bytes,
+/// positions count them in UTF-16 code units.
+pub fn point_to_position(source: &str, point: Point) -> Position {
+    let byte = line_start(source, point.row)
+        .map(|line_start| line_start + point.column)
+        .filter(|byte| source.is_char_boundary(*byte));
+    match byte {
+        Some(byte) => byte_position(source, point.row, byte),
+        None => Position::new(point.row as u64, point.column as u64),
+    }
+}
+
+/// Tree-sitter point of the LSP position, columns past the end of the line point to the line end.
+pub fn position_to_point(source: &str, position: Position) -> Point {
+    let row = position.line as usize;
+    let line = match line_start(source, row) {
+        Some(line_start) => source[line_start..].split('\n').next().unwrap_or_default(),
+        None => return Point::new(row, position.character as usize),
+    };
+    let mut utf16_column = 0;
+    let column = line
+        .char_indices()
+        .find(|(_, c)| {
+            let is_reached = utf16_column >= position.character as usize;
+            utf16_column += c.len_utf16();
+            is_reached
+        })
+        .map(|(index, _)| index)
+        .unwrap_or_else(|| line.len());
+    Point::new(row, column)
+}
+
+pub fn node_range(source: &str, node: Node) -> Range {
+    Range::new(
+        byte_position(source, node.start_position().row, node.start_byte()),
+        byte_position(source, node.end_position().row, node.end_byte()),
+    )
+}
+
+/// Position of the `byte` of the `source`, which is on the `row`.
+fn byte_position(source: &str, row: usize, byte: usize) -> Position {
+    let byte = usize::min(byte, source.len());
+    let line_start = source[..byte].rfind('\n').map_or(0, |index| index + 1);
+    let column = source[line_start..byte].encode_utf16().count();
+    Position::new(row as u64, column as u64)
+}
+
+fn line_start(source: &str, row: usize) -> Option<usize> {
+    if row == 0 {
+        return Some(0);
+    }
+    source
+        .match_indices('\n')
+        .nth(row - 1)
+        .map(|(index, _)| index + 1)
+}
+
+pub fn node_text<'a>(source: &'a str, node: Node) -> &'a str {
+    &source[node.start_byte()..node.end_byte()]
+}
+
+pub fn ancestors<'tree>(node: Node<'tree>) -> impl Iterator<Item = Node<'tree>> {
+    std::iter::successors(node.parent(), |node| node.parent())
+}
+
+/// All nodes of the subtree, in source order.
+pub fn descendants(root: Node) -> Vec<Node> {
+    let mut nodes = vec![];
+    let mut cursor = root.walk();
+    loop {
+        nodes.push(cursor.node());
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+        loop {
+            if !cursor.goto_parent() {
+                return nodes;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+}
+
+/// Identifier under the cursor. Cursor right after the last character of identifier counts too.
+pub fn ident_at_position(source_file: &SourceFile, pos: (usize, usize)) -> Option<Node> {
+    let root = source_file.tree.root_node();
+    let position = Position::new(pos.0 as u64, pos.1 as u64);
+    let point = position_to_point(source_file.source(), position);
+
+    let mut points = vec![point];
+    if point.column > 0 {
+        points.push(Point::new(point.row, point.column - 1));
+    }
+    points.into_iter().find_map(|point| {
+        root.named_descendant_for_point_range(point, point)
+            .filter(is_ident)
+    })
+}
+
+/// Source text on a single line, with runs of whitespace collapsed.
+pub fn single_line(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(",)", ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "script {\n    /* ключ */ fun main() {}\n}\n";
+
+    #[test]
+    fn test_point_columns_are_converted_to_utf16_code_units() {
+        // each cyrillic letter takes two bytes and one UTF-16 code unit
+        assert_eq!(
+            point_to_position(SOURCE, Point::new(1, 19)),
+            Position::new(1, 15)
+        );
+        assert_eq!(
+            position_to_point(SOURCE, Position::new(1, 15)),
+            Point::new(1, 19)
+        );
+        assert_eq!(
+            point_to_position(SOURCE, Point::new(2, 0)),
+            Position::new(2, 0)
+        );
+    }
+
+    #[test]
+    fn test_position_past_the_line_end_points_to_the_line_end() {
+        assert_eq!(
+            position_to_point(SOURCE, Position::new(1, 100)),
+            Point::new(1, 32)
+        );
+    }
+}
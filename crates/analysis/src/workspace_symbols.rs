@@ -0,0 +1,122 @@
+use lsp_types::{Location, SymbolInformation, SymbolKind, Url};
+
+use crate::db::RootDatabase;
+use crate::items::{Item, ItemKind};
+
+const MAX_SYMBOLS: usize = 128;
+
+/// Modules, structs and functions of all known files, which names fuzzy match the query.
+/// Queries with `::`, like `Signer::addr`, are matched against the names qualified with the module.
+/// Symbol names are fully qualified, like `0x1::Signer::address_of`.
+pub fn workspace_symbols(db: &RootDatabase, query: &str) -> Vec<SymbolInformation> {
+    let index = db.item_index();
+    let mut matches = vec![];
+    for module in index.modules() {
+        let module_path = module.path();
+        if let Some(score) = fuzzy_score(query, &module.name) {
+            matches.push((score, module_path.clone(), &module.def, None));
+        }
+        for item in &module.items {
+            let name = if query.contains("::") {
+                format!("{}::{}", module.name, item.name)
+            } else {
+                item.name.clone()
+            };
+            if let Some(score) = fuzzy_score(query, &name) {
+                let path = format!("{}::{}", module_path, item.name);
+                matches.push((score, path, item, Some(module_path.clone())));
+            }
+        }
+    }
+    matches.sort_by(|(l_score, l_path, _, _), (r_score, r_path, _, _)| {
+        l_score.cmp(r_score).then_with(|| l_path.cmp(r_path))
+    });
+    matches
+        .into_iter()
+        .filter_map(|(_, path, item, container_name)| {
+            let uri = match Url::from_file_path(item.fpath) {
+                Ok(uri) => uri,
+                Err(_) => {
+                    log::warn!(
+                        "Symbol {} is skipped, path {:?} is not absolute",
+                        path,
+                        item.fpath
+                    );
+                    return None;
+                }
+            };
+            Some(SymbolInformation {
+                name: path,
+                kind: symbol_kind(item),
+                deprecated: None,
+                location: Location::new(uri, item.name_range),
+                container_name,
+            })
+        })
+        .take(MAX_SYMBOLS)
+        .collect()
+}
+
+fn symbol_kind(item: &Item) -> SymbolKind {
+    match item.kind {
+        ItemKind::Module => SymbolKind::Module,
+        ItemKind::Struct | ItemKind::Resource | ItemKind::NativeStruct => SymbolKind::Struct,
+        _ => SymbolKind::Function,
+    }
+}
+
+/// Lower is better, `None` if the query characters are not a subsequence of the name.
+/// Exact matches come first, then prefix matches, then the rest.
+fn fuzzy_score(query: &str, name: &str) -> Option<u8> {
+    let query = query.to_lowercase();
+    let name = name.to_lowercase();
+    if query == name {
+        return Some(0);
+    }
+    if name.starts_with(&query) {
+        return Some(1);
+    }
+    let mut name_chars = name.chars();
+    let is_subsequence = query
+        .chars()
+        .all(|query_char| name_chars.any(|name_char| name_char == query_char));
+    if is_subsequence {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use utils::{FileId, FilesSourceText};
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("address_of", "address_of"), Some(0));
+        assert_eq!(fuzzy_score("ADDR", "address_of"), Some(1));
+        assert_eq!(fuzzy_score("adof", "address_of"), Some(2));
+        assert_eq!(fuzzy_score("", "address_of"), Some(1));
+        assert_eq!(fuzzy_score("foa", "address_of"), None);
+    }
+
+    #[test]
+    fn test_symbols_of_files_without_absolute_paths_are_skipped() {
+        let mut files = FilesSourceText::new();
+        files.insert(
+            FileId::new("relative.move"),
+            "address 0x2 { module A { fun f() {} } }".to_string(),
+        );
+        files.insert(
+            FileId::new("/absolute.move"),
+            "address 0x2 { module B { fun f() {} } }".to_string(),
+        );
+        let db = RootDatabase::new(Config::default(), files);
+
+        let symbols = workspace_symbols(&db, "f");
+        let names: Vec<_> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(names, vec!["0x2::B::f"]);
+    }
+}
@@ -1,16 +1,38 @@
 use crate::lang::into_exec_compiler_error;
-use crate::lang::{check_defs, replace_sender_placeholder};
-use crate::shared::errors::{CompilerError, ExecCompilerError, FileSourceMap, ProjectSourceMap};
-use crate::shared::ProvidedAccountAddress;
+use crate::lang::{check_defs, placeholder_templates, replace_address_placeholder};
+use crate::shared::addresses::check_hex_address_lengths;
+use crate::shared::errors::{
+    CompilerError, CompilerErrorPart, ExecCompilerError, FileSourceMap, Location,
+    ProjectSourceMap,
+};
+use crate::shared::{AddressPlaceholders, ProvidedAccountAddress};
 use anyhow::Result;
+use move_core_types::account_address::AccountAddress;
 use move_lang::parser::ast::Definition;
 use move_lang::parser::syntax;
 use move_lang::strip_comments_and_verify;
-use utils::MoveFile;
+use utils::{FileId, MoveFile};
+
+/// Definitions of the file, along with the substitutions made in its text before parsing.
+pub type ParseResult = Result<(Vec<Definition>, FileSourceMap), ExecCompilerError>;
 
 pub trait Dialect {
     fn name(&self) -> &str;
 
+    /// Length of account addresses in bytes.
+    fn address_length(&self) -> usize;
+
+    /// Sender address to use if none is configured.
+    fn default_sender(&self) -> &str;
+
+    /// Builtin functions, available in function bodies without imports.
+    fn builtins(&self) -> &[&str];
+
+    /// Templates in the source, replaced with the sender address before compilation.
+    fn sender_placeholders(&self) -> &[&str] {
+        &["{{sender}}", "{{ sender }}"]
+    }
+
     fn normalize_account_address(&self, addr: &str) -> Result<ProvidedAccountAddress>;
 
     fn replace_addresses(&self, source_text: &str, source_map: &mut FileSourceMap) -> String;
@@ -19,27 +41,49 @@ pub trait Dialect {
         &self,
         file: MoveFile,
         sender: &ProvidedAccountAddress,
-    ) -> Result<(Vec<Definition>, FileSourceMap), ExecCompilerError> {
+        placeholders: &AddressPlaceholders,
+    ) -> ParseResult {
         let (fname, mut source_text) = file;
 
         let mut file_source_map = FileSourceMap::default();
-        source_text = replace_sender_placeholder(
+        source_text = replace_address_placeholder(
             source_text,
             &sender.normalized_original,
+            self.sender_placeholders(),
             &mut file_source_map,
         );
+        for (name, address) in placeholders {
+            source_text = replace_address_placeholder(
+                source_text,
+                &address.normalized_original,
+                &placeholder_templates(name),
+                &mut file_source_map,
+            );
+        }
         source_text = self.replace_addresses(&source_text, &mut file_source_map);
 
-        let (source_text, comment_map) =
-            strip_comments_and_verify(fname, &source_text).map_err(|errors| {
+        let (source_text, comment_map) = strip_comments_and_verify(fname.path(), &source_text)
+            .map_err(|errors| {
                 into_exec_compiler_error(
                     errors,
                     ProjectSourceMap::with_file_map(fname, FileSourceMap::default()),
                 )
             })?;
 
-        let (defs, _) =
-            syntax::parse_file_string(fname, &source_text, comment_map).map_err(|errors| {
+        // the compiler accepts literals up to its own address length, which might be wider.
+        // Comments are already blanked out, with the positions of the rest of the text kept
+        if self.address_length() < AccountAddress::LENGTH {
+            let errors = check_hex_address_lengths(fname, &source_text, self.address_length());
+            if !errors.is_empty() {
+                return Err(ExecCompilerError(
+                    errors,
+                    ProjectSourceMap::with_file_map(fname, file_source_map),
+                ));
+            }
+        }
+
+        let (defs, _) = syntax::parse_file_string(fname.path(), &source_text, comment_map)
+            .map_err(|errors| {
                 into_exec_compiler_error(
                     errors,
                     ProjectSourceMap::with_file_map(fname, file_source_map.clone()),
@@ -51,13 +95,14 @@ pub trait Dialect {
     fn parse_files(
         &self,
         current_file: MoveFile,
-        deps: &[MoveFile],
+        deps: Vec<(FileId, ParseResult)>,
         sender: &ProvidedAccountAddress,
+        placeholders: &AddressPlaceholders,
     ) -> Result<(Vec<Definition>, Vec<Definition>, ProjectSourceMap), ExecCompilerError> {
         let mut exec_compiler_error = ExecCompilerError::default();
 
         let mut project_offsets_map = ProjectSourceMap::default();
-        let script_defs = match self.parse_file(current_file.clone(), &sender) {
+        let script_defs = match self.parse_file(current_file.clone(), &sender, placeholders) {
             Ok((defs, offsets_map)) => {
                 project_offsets_map.0.insert(current_file.0, offsets_map);
                 defs
@@ -69,10 +114,10 @@ pub trait Dialect {
         };
 
         let mut dep_defs = vec![];
-        for dep_file in deps.iter() {
-            let defs = match self.parse_file(dep_file.clone(), &sender) {
+        for (dep_fpath, parsed) in deps {
+            let defs = match parsed {
                 Ok((defs, offsets_map)) => {
-                    project_offsets_map.0.insert(dep_file.0, offsets_map);
+                    project_offsets_map.0.insert(dep_fpath, offsets_map);
                     defs
                 }
                 Err(error) => {
@@ -93,12 +138,37 @@ pub trait Dialect {
         current: MoveFile,
         deps: Vec<MoveFile>,
         sender: &ProvidedAccountAddress,
+        placeholders: &AddressPlaceholders,
+    ) -> Result<(), Vec<CompilerError>> {
+        let parsed_deps = deps
+            .into_iter()
+            .map(|dep_file| (dep_file.0, self.parse_file(dep_file, sender, placeholders)))
+            .collect();
+        self.check_parsed_with_compiler(current, parsed_deps, sender, placeholders)
+    }
+
+    /// Same as `check_with_compiler`, with dependencies parsed beforehand by `parse_file`.
+    fn check_parsed_with_compiler(
+        &self,
+        current: MoveFile,
+        deps: Vec<(FileId, ParseResult)>,
+        sender: &ProvidedAccountAddress,
+        placeholders: &AddressPlaceholders,
     ) -> Result<(), Vec<CompilerError>> {
+        let sender_address = sender.as_address().map_err(|err| {
+            vec![CompilerError::new(vec![CompilerErrorPart {
+                location: Location {
+                    fpath: current.0,
+                    span: (0, 0),
+                },
+                message: format!("Invalid sender address {:?}: {}", sender.original, err),
+            }])]
+        })?;
         let (script_defs, dep_defs, offsets_map) = self
-            .parse_files(current, &deps, sender)
+            .parse_files(current, deps, sender, placeholders)
             .map_err(|errors| errors.transform_with_source_map())?;
 
-        match check_defs(script_defs, dep_defs, sender.as_address()) {
+        match check_defs(script_defs, dep_defs, sender_address) {
             Ok(_) => Ok(()),
             Err(errors) => {
                 Err(into_exec_compiler_error(errors, offsets_map).transform_with_source_map())
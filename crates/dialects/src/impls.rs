@@ -1,20 +1,147 @@
 use crate::base::Dialect;
+use crate::registry::{AddressFormat, DialectSpec};
+use crate::shared::addresses::{normalize_hex_address, widen_hex_addresses};
+use crate::shared::bech32::{bech32_into_libra, replace_bech32_addresses, DEFAULT_HRP};
 use crate::shared::errors::FileSourceMap;
 use crate::shared::ProvidedAccountAddress;
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use move_core_types::account_address::AccountAddress;
 
-#[derive(Default)]
-pub struct LibraDialect;
+const SENDER_PLACEHOLDERS: &[&str] = &["{{sender}}", "{{ sender }}"];
 
-impl Dialect for LibraDialect {
+const LIBRA_BUILTINS: &[&str] = &[
+    "borrow_global",
+    "emit_event",
+    "borrow_global_mut",
+    "exists",
+    "move_from",
+    "move_to_sender",
+];
+
+pub const LIBRA: DialectSpec = DialectSpec {
+    name: "libra",
+    address_length: AccountAddress::LENGTH,
+    address_formats: &[AddressFormat::Hex { widen: false }],
+    sender_placeholders: SENDER_PLACEHOLDERS,
+    builtins: LIBRA_BUILTINS,
+    default_sender: "0x1",
+};
+
+pub const STARCOIN: DialectSpec = DialectSpec {
+    name: "starcoin",
+    address_length: 16,
+    address_formats: &[AddressFormat::Hex { widen: true }],
+    sender_placeholders: SENDER_PLACEHOLDERS,
+    builtins: &[
+        "borrow_global",
+        "borrow_global_mut",
+        "exists",
+        "freeze",
+        "move_from",
+        "move_to",
+    ],
+    // association account
+    default_sender: "0xa550c18",
+};
+
+/// Dialect with bech32 addresses, like `wallet1me0cdn52672y7feddy7tgcj6j4dkzq2mveywu4`.
+/// Hex addresses are accepted too.
+pub const DFINANCE: DialectSpec = DialectSpec {
+    name: "dfinance",
+    address_length: 20,
+    address_formats: &[
+        AddressFormat::Bech32 { hrp: DEFAULT_HRP },
+        AddressFormat::Hex { widen: false },
+    ],
+    sender_placeholders: SENDER_PLACEHOLDERS,
+    builtins: LIBRA_BUILTINS,
+    default_sender: "0x1",
+};
+
+/// Dialect, described by the `DialectSpec`.
+pub struct SpecDialect {
+    spec: DialectSpec,
+    bech32_hrp: Option<String>,
+}
+
+impl SpecDialect {
+    pub fn new(spec: DialectSpec) -> SpecDialect {
+        SpecDialect {
+            spec,
+            bech32_hrp: None,
+        }
+    }
+
+    /// Overrides prefix of bech32 address literals, if dialect has them.
+    pub fn with_bech32_hrp(mut self, hrp: &str) -> SpecDialect {
+        self.bech32_hrp = Some(hrp.to_string());
+        self
+    }
+
+    fn bech32_hrp(&self) -> Option<&str> {
+        self.spec
+            .address_formats
+            .iter()
+            .find_map(|format| match format {
+                AddressFormat::Bech32 { hrp } => Some(self.bech32_hrp.as_deref().unwrap_or(hrp)),
+                _ => None,
+            })
+    }
+
+    fn hex_format(&self) -> Option<AddressFormat> {
+        self.spec
+            .address_formats
+            .iter()
+            .copied()
+            .find(|format| matches!(format, AddressFormat::Hex { .. }))
+    }
+}
+
+impl Dialect for SpecDialect {
     fn name(&self) -> &str {
-        "libra"
+        self.spec.name
+    }
+
+    fn address_length(&self) -> usize {
+        self.spec.address_length
+    }
+
+    fn default_sender(&self) -> &str {
+        self.spec.default_sender
+    }
+
+    fn builtins(&self) -> &[&str] {
+        self.spec.builtins
+    }
+
+    fn sender_placeholders(&self) -> &[&str] {
+        self.spec.sender_placeholders
     }
 
     fn normalize_account_address(&self, addr: &str) -> Result<ProvidedAccountAddress> {
-        let address = AccountAddress::from_hex_literal(&addr)?;
-        let normalized_address = format!("0x{}", address);
+        ensure!(
+            self.address_length() <= AccountAddress::LENGTH,
+            "Address length of the dialect {:?} should be at most {} bytes",
+            self.spec.name,
+            AccountAddress::LENGTH
+        );
+        if let Some(hrp) = self.bech32_hrp() {
+            // bech32 form is kept in the source, and replaced along with other addresses
+            if let Ok(lowered_address) = bech32_into_libra(addr, hrp) {
+                return Ok(ProvidedAccountAddress::new(
+                    addr.to_string(),
+                    addr.to_string(),
+                    normalize_hex_address(&lowered_address, self.address_length())?,
+                ));
+            }
+        }
+        ensure!(
+            self.hex_format().is_some(),
+            "Invalid {} address {:?}",
+            self.spec.name,
+            addr
+        );
+        let normalized_address = normalize_hex_address(addr, self.address_length())?;
         Ok(ProvidedAccountAddress::new(
             addr.to_string(),
             normalized_address.clone(),
@@ -22,8 +149,14 @@ impl Dialect for LibraDialect {
         ))
     }
 
-    fn replace_addresses(&self, source_text: &str, _source_map: &mut FileSourceMap) -> String {
-        // crate::shared::addresses::replace_16_bytes_libra(source_text, source_map)
-        source_text.to_string()
+    fn replace_addresses(&self, source_text: &str, source_map: &mut FileSourceMap) -> String {
+        let mut source_text = match self.bech32_hrp() {
+            Some(hrp) => replace_bech32_addresses(source_text, hrp, source_map),
+            None => source_text.to_string(),
+        };
+        if let Some(AddressFormat::Hex { widen: true }) = self.hex_format() {
+            source_text = widen_hex_addresses(&source_text, self.address_length(), source_map);
+        }
+        source_text
     }
 }
@@ -11,9 +11,10 @@ use move_lang::{
 };
 
 use crate::shared::errors::{
-    len_difference, CompilerError, CompilerErrorPart, ExecCompilerError, FileSourceMap, Location,
+    CompilerError, CompilerErrorPart, ExecCompilerError, FileSourceMap, Location,
     ProjectSourceMap,
 };
+use utils::FileId;
 
 fn from_compiler_error(comp_error: CompilerError) -> Error {
     comp_error
@@ -26,7 +27,7 @@ fn from_compiler_error(comp_error: CompilerError) -> Error {
             } = part;
             (
                 Loc::new(
-                    fpath,
+                    fpath.path(),
                     codespan::Span::new(ByteIndex(span.0 as u32), ByteIndex(span.1 as u32)),
                 ),
                 message,
@@ -45,14 +46,14 @@ fn into_compiler_error(error: Error) -> CompilerError {
     for (loc, message) in error {
         let part = CompilerErrorPart {
             location: Location {
-                fpath: loc.file(),
+                fpath: FileId::new(loc.file()),
                 span: (loc.span().start().to_usize(), loc.span().end().to_usize()),
             },
             message,
         };
         parts.push(part);
     }
-    CompilerError { parts }
+    CompilerError::new(parts)
 }
 
 pub fn into_exec_compiler_error(
@@ -66,22 +67,24 @@ pub fn into_exec_compiler_error(
     ExecCompilerError(compiler_errors, offsets_map)
 }
 
-/// replace {{sender}} and {{ sender }} inside source code
-pub fn replace_sender_placeholder(
+/// Templates of the named placeholder, `{{name}}` and `{{ name }}`.
+pub fn placeholder_templates(name: &str) -> [String; 2] {
+    [format!("{{{{{}}}}}", name), format!("{{{{ {} }}}}", name)]
+}
+
+/// replace placeholder templates, like {{sender}}, with the address inside source code
+pub fn replace_address_placeholder<T: AsRef<str>>(
     s: String,
-    sender: &str,
+    address: &str,
+    templates: &[T],
     file_source_map: &mut FileSourceMap,
 ) -> String {
-    assert!(
-        sender.len() > 12,
-        "Sender address length is too short: {}",
-        sender.len()
-    );
     let mut new_s = s;
-    for template in &["{{sender}}", "{{ sender }}"] {
+    for template in templates {
+        let template = template.as_ref();
         while let Some(pos) = new_s.find(template) {
-            new_s.replace_range(pos..pos + template.len(), sender);
-            file_source_map.insert_layer(pos + sender.len(), len_difference(template, sender));
+            new_s.replace_range(pos..pos + template.len(), address);
+            file_source_map.insert_replacement(pos, template, address);
         }
     }
     new_s
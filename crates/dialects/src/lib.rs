@@ -1,30 +1,41 @@
 use anyhow::Result;
 
 use crate::base::Dialect;
-use crate::impls::LibraDialect;
+use crate::impls::{SpecDialect, LIBRA};
+use crate::registry::{dialect_names, dialect_spec, DialectSpec};
 
 use serde::export::fmt::Debug;
+use serde::{Deserialize, Deserializer};
 
 use std::str::FromStr;
 
 pub mod base;
 pub mod impls;
 pub mod lang;
+pub mod registry;
 pub mod shared;
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum DialectName {
-    Libra,
-    Starcoin,
-}
+/// Name of the dialect from the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DialectName(&'static str);
 
 impl DialectName {
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    pub fn spec(&self) -> DialectSpec {
+        dialect_spec(self.0).unwrap_or_else(|| panic!("Dialect {:?} is not registered", self.0))
+    }
+
     pub fn get_dialect(&self) -> Box<dyn Dialect> {
-        match self {
-            DialectName::Libra => Box::new(LibraDialect::default()),
-            DialectName::Starcoin => Box::new(LibraDialect::default()),
-        }
+        Box::new(SpecDialect::new(self.spec()))
+    }
+}
+
+impl Default for DialectName {
+    fn default() -> Self {
+        DialectName(LIBRA.name)
     }
 }
 
@@ -32,10 +43,23 @@ impl FromStr for DialectName {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "libra" => Ok(DialectName::Libra),
-            "starcoin" => Ok(DialectName::Starcoin),
-            _ => Err(anyhow::format_err!("Invalid dialect {:?}", s)),
+        match dialect_spec(s) {
+            Some(spec) => Ok(DialectName(spec.name)),
+            None => Err(anyhow::format_err!(
+                "Invalid dialect {:?}, available dialects are {:?}",
+                s,
+                dialect_names()
+            )),
         }
     }
 }
+
+impl<'de> Deserialize<'de> for DialectName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use anyhow::{ensure, Result};
+use lazy_static::lazy_static;
+use move_core_types::account_address::AccountAddress;
+
+use crate::impls::{DFINANCE, LIBRA, STARCOIN};
+
+/// Address literal, accepted in source files and in the `sender_address` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// `0x` prefixed hex literal. With `widen`, shorter literals are padded with zeros
+    /// up to the address length before compilation.
+    Hex { widen: bool },
+    /// bech32 literal, like `wallet1me0cdn52672y7feddy7tgcj6j4dkzq2mveywu4`.
+    /// Prefix can be overridden with the `bech32_hrp` setting.
+    Bech32 { hrp: &'static str },
+}
+
+/// Declarative description of the dialect.
+#[derive(Debug, Clone, Copy)]
+pub struct DialectSpec {
+    /// Name to select the dialect by with `"dialect"` setting.
+    pub name: &'static str,
+    /// Account address length in bytes, at most `AccountAddress::LENGTH`.
+    /// Wider addresses, like 32-byte ones, can't be represented by the compiler in use,
+    /// so `register_dialect` rejects such dialects.
+    pub address_length: usize,
+    pub address_formats: &'static [AddressFormat],
+    /// Templates in the source, replaced with the sender address before compilation.
+    pub sender_placeholders: &'static [&'static str],
+    /// Builtin functions, available in function bodies without imports.
+    pub builtins: &'static [&'static str],
+    /// Sender address to use if none is configured.
+    pub default_sender: &'static str,
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<&'static str, DialectSpec>> = RwLock::new(
+        [LIBRA, STARCOIN, DFINANCE]
+            .iter()
+            .map(|spec| (spec.name, *spec))
+            .collect()
+    );
+}
+
+/// Makes the dialect available by its name, replaces previously registered one with the same name.
+/// Dialects with addresses wider than the compiler ones are rejected.
+pub fn register_dialect(spec: DialectSpec) -> Result<()> {
+    ensure!(
+        spec.address_length <= AccountAddress::LENGTH,
+        "Address length of the dialect {:?} should be at most {} bytes",
+        spec.name,
+        AccountAddress::LENGTH
+    );
+    REGISTRY.write().unwrap().insert(spec.name, spec);
+    Ok(())
+}
+
+pub fn dialect_spec(name: &str) -> Option<DialectSpec> {
+    REGISTRY.read().unwrap().get(name).copied()
+}
+
+pub fn dialect_names() -> Vec<&'static str> {
+    let mut names: Vec<_> = REGISTRY.read().unwrap().keys().copied().collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Dialect;
+    use crate::impls::SpecDialect;
+
+    const TESTNET: DialectSpec = DialectSpec {
+        name: "testnet",
+        address_length: 20,
+        address_formats: &[AddressFormat::Hex { widen: true }],
+        sender_placeholders: &["{{sender}}"],
+        builtins: &["exists"],
+        default_sender: "0x1",
+    };
+
+    #[test]
+    fn builtin_dialects_are_registered() {
+        for name in &["libra", "starcoin", "dfinance"] {
+            assert_eq!(dialect_spec(name).unwrap().name, *name);
+        }
+        assert!(dialect_spec("unknown").is_none());
+    }
+
+    #[test]
+    fn register_dialect_from_outside() {
+        register_dialect(TESTNET).unwrap();
+        assert!(dialect_names().contains(&"testnet"));
+        assert_eq!(dialect_spec("testnet").unwrap().address_length, 20);
+    }
+
+    #[test]
+    fn dialect_with_too_wide_addresses_is_rejected() {
+        let spec = DialectSpec {
+            name: "widenet",
+            address_length: 32,
+            ..TESTNET
+        };
+        assert!(register_dialect(spec).is_err());
+        assert!(dialect_spec("widenet").is_none());
+
+        // unregistered spec is checked when addresses are normalized
+        let dialect = SpecDialect::new(spec);
+        assert!(dialect.normalize_account_address("0x1").is_err());
+    }
+}
@@ -1,60 +1,119 @@
-use crate::shared::errors::FileSourceMap;
+use crate::shared::errors::{CompilerError, CompilerErrorPart, FileSourceMap, Location};
+use anyhow::{ensure, Result};
 use lazy_static::lazy_static;
-use move_core_types::account_address::AccountAddress;
 use regex::Regex;
+use utils::FileId;
 
 lazy_static! {
-    static ref LIBRA_16_BYTES_REGEX: Regex = Regex::new(r"(0x[0-9a-f]{1,32})[^0-9a-f]").unwrap();
+    static ref HEX_ADDRESS_REGEX: Regex = Regex::new(r"\b0x[0-9a-fA-F]+\b").unwrap();
 }
 
-pub fn replace_16_bytes_libra(source: &str, file_source_map: &mut FileSourceMap) -> String {
-    let mut transformed_source = source.to_string();
-
-    while let Some(mat) = LIBRA_16_BYTES_REGEX.captures(&transformed_source.clone()) {
-        let item = mat.get(1).unwrap();
+/// Validates hex address literal and pads it with zeros to `address_length` bytes.
+pub fn normalize_hex_address(addr: &str, address_length: usize) -> Result<String> {
+    ensure!(addr.starts_with("0x"), "Pass address with 0x prefix");
+    let hex_digits = &addr[2..];
+    ensure!(
+        !hex_digits.is_empty() && hex_digits.chars().all(|c| c.is_ascii_hexdigit()),
+        "Invalid hex address {:?}",
+        addr
+    );
+    ensure!(
+        hex_digits.len() <= address_length * 2,
+        "Address should be at most {} bytes long",
+        address_length
+    );
+    Ok(format!(
+        "0x{:0>width$}",
+        hex_digits.to_lowercase(),
+        width = address_length * 2
+    ))
+}
 
+/// Pads shorter hex address literals with zeros up to `address_length` bytes.
+/// Longer ones are left as is, to be reported by `check_hex_address_lengths`.
+pub fn widen_hex_addresses(
+    source: &str,
+    address_length: usize,
+    file_source_map: &mut FileSourceMap,
+) -> String {
+    let mut transformed_source = String::with_capacity(source.len());
+    let mut last_end = 0;
+    for item in HEX_ADDRESS_REGEX.find_iter(source) {
         let orig_address = item.as_str();
-        let account_address = AccountAddress::from_hex_literal(orig_address).unwrap();
-        let repl_address = format!("0x00000000{}", account_address);
-
-        file_source_map.insert_address_layer(
-            item.end(),
+        if orig_address.len() - 2 >= address_length * 2 {
+            continue;
+        }
+        let repl_address = format!(
+            "0x{:0>width$}",
+            &orig_address[2..],
+            width = address_length * 2
+        );
+        transformed_source.push_str(&source[last_end..item.start()]);
+        file_source_map.insert_address_replacement(
+            transformed_source.len(),
             orig_address.to_string(),
             repl_address.clone(),
         );
-        transformed_source.replace_range(item.range(), &repl_address);
+        transformed_source.push_str(&repl_address);
+        last_end = item.end();
     }
+    transformed_source.push_str(&source[last_end..]);
     transformed_source
 }
 
+/// Errors at the hex address literals longer than `address_length` bytes.
+pub fn check_hex_address_lengths(
+    fpath: FileId,
+    source: &str,
+    address_length: usize,
+) -> Vec<CompilerError> {
+    HEX_ADDRESS_REGEX
+        .find_iter(source)
+        .filter(|item| item.as_str().len() - 2 > address_length * 2)
+        .map(|item| {
+            CompilerError::new(vec![CompilerErrorPart {
+                location: Location {
+                    fpath,
+                    span: (item.start(), item.end()),
+                },
+                message: format!(
+                    "Address {} is too long, addresses should be at most {} bytes long",
+                    item.as_str(),
+                    address_length
+                ),
+            }])
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn replace_libra_16_byte_address() {
+    fn widen_16_bytes_address_to_20_bytes() {
         let source = "use 0x00000000000000001111111111111111;";
-        let replaced = replace_16_bytes_libra(source, &mut FileSourceMap::default());
+        let replaced = widen_hex_addresses(source, 20, &mut FileSourceMap::default());
         assert_eq!(replaced, "use 0x0000000000000000000000001111111111111111;");
     }
 
     #[test]
-    fn replace_multiple_addresses() {
+    fn widen_multiple_addresses() {
         let source =
             "use 0x00000000000000001111111111111111; \n use 0x00000000000000001111111111111112;";
-        let replaced = replace_16_bytes_libra(source, &mut FileSourceMap::default());
+        let replaced = widen_hex_addresses(source, 20, &mut FileSourceMap::default());
         assert_eq!(replaced, "use 0x0000000000000000000000001111111111111111; \n use 0x0000000000000000000000001111111111111112;");
     }
 
     #[test]
-    fn dont_replace_20_bytes_address() {
+    fn dont_widen_address_of_full_length() {
         let source = "use 0x0000000000000000000000001111111111111111;";
-        let replaced = replace_16_bytes_libra(source, &mut FileSourceMap::default());
+        let replaced = widen_hex_addresses(source, 20, &mut FileSourceMap::default());
         assert_eq!(replaced, "use 0x0000000000000000000000001111111111111111;");
     }
 
     #[test]
-    fn replace_minified_addresses() {
+    fn widen_minified_addresses() {
         let source = r"use 0x0;
         use 0x1;
         use 0x11;
@@ -62,7 +121,7 @@ mod tests {
         use 0x11111111;
         use 0x1111111111111111;
         use 0x00000000000000001111111111111111;";
-        let replaced = replace_16_bytes_libra(source, &mut FileSourceMap::default());
+        let replaced = widen_hex_addresses(source, 20, &mut FileSourceMap::default());
         assert_eq!(
             replaced,
             r"use 0x0000000000000000000000000000000000000000;
@@ -74,4 +133,58 @@ mod tests {
         use 0x0000000000000000000000001111111111111111;"
         )
     }
+
+    #[test]
+    fn widen_short_addresses_to_16_bytes() {
+        let source =
+            "use 0x1::Signer; let _ = 0x12345;\n use 0x00000000000000001111111111111111;";
+        let mut source_map = FileSourceMap::default();
+        let replaced = widen_hex_addresses(source, 16, &mut source_map);
+        assert_eq!(
+            replaced,
+            "use 0x00000000000000000000000000000001::Signer; \
+             let _ = 0x00000000000000000000000000012345;\n \
+             use 0x00000000000000001111111111111111;"
+        );
+
+        let start = replaced.find("0x00000000000000000000000000012345").unwrap();
+        assert_eq!(source_map.translate_span((start, start + 34)), (25, 32));
+    }
+
+    #[test]
+    fn dont_widen_address_longer_than_address_length() {
+        let source = "use 0x0000000000000000000000001111111111111111;";
+        let replaced = widen_hex_addresses(source, 16, &mut FileSourceMap::default());
+        assert_eq!(replaced, source);
+    }
+
+    #[test]
+    fn addresses_longer_than_address_length_are_reported() {
+        let fpath = FileId::new("/script.move");
+        let source = "use 0x1::M; use 0x000000000000000011111111111111111111;";
+        let errors = check_hex_address_lengths(fpath, source, 16);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].parts[0].location.span, (16, 54));
+        assert_eq!(
+            errors[0].parts[0].message,
+            "Address 0x000000000000000011111111111111111111 is too long, \
+             addresses should be at most 16 bytes long"
+        );
+
+        assert!(check_hex_address_lengths(fpath, source, 20).is_empty());
+    }
+
+    #[test]
+    fn normalize_hex_address_to_address_length() {
+        assert_eq!(
+            normalize_hex_address("0xA550C18", 16).unwrap(),
+            "0x0000000000000000000000000a550c18"
+        );
+        assert_eq!(
+            normalize_hex_address("0x1", 20).unwrap(),
+            "0x0000000000000000000000000000000000000001"
+        );
+        assert!(normalize_hex_address(&format!("0x{}", "1".repeat(34)), 16).is_err());
+        assert!(normalize_hex_address("1", 16).is_err());
+    }
 }
@@ -5,23 +5,30 @@ use lazy_static::lazy_static;
 use crate::shared::errors::FileSourceMap;
 use regex::Regex;
 
-pub static HRP: &str = "wallet";
+/// Human-readable prefix of the addresses, if none is configured.
+pub static DEFAULT_HRP: &str = "wallet";
 
 lazy_static! {
-    static ref BECH32_REGEX: Regex = Regex::new(
-        r#"[\s=]+(["!#$%&'()*+,\-./0123456789:;<=>?@A-Z\[\\\]^_`a-z{|}~]{1,83}1[A-Z0-9a-z&&[^boi1]]{6,})"#,
-    )
-    .unwrap();
+    // alphanumeric prefix, so that the punctuation and the replaced addresses next to it
+    // don't become a part of the match
+    static ref BECH32_REGEX: Regex =
+        Regex::new(r"\b([0-9A-Za-z]{1,83}1[A-Z0-9a-z&&[^boi1]]{6,})\b").unwrap();
 }
 
-pub fn bech32_into_libra(address: &str) -> Result<String> {
-    let (_, data_bytes) = bech32::decode(address)?;
+pub fn bech32_into_libra(address: &str, hrp: &str) -> Result<String> {
+    let (address_hrp, data_bytes) = bech32::decode(address)?;
+    ensure!(
+        address_hrp == hrp.to_lowercase(),
+        "Address prefix should be {:?}, got {:?}",
+        hrp,
+        address_hrp
+    );
     let data = bech32::convert_bits(&data_bytes, 5, 8, true)?;
     let libra_address_hex = hex::encode(&data).trim_start_matches('0').to_string();
     Ok(format!("0x{}", libra_address_hex))
 }
 
-pub fn libra_into_bech32(libra_address: &str) -> Result<String> {
+pub fn libra_into_bech32(libra_address: &str, hrp: &str) -> Result<String> {
     ensure!(
         libra_address.starts_with("0x"),
         "Pass address with 0x prefix"
@@ -33,13 +40,18 @@ pub fn libra_into_bech32(libra_address: &str) -> Result<String> {
         .map(u5::try_from_u8)
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(bech32::encode(&HRP, data)?)
+    Ok(bech32::encode(hrp, data)?)
 }
 
-pub fn replace_bech32_addresses(source: &str, file_source_map: &mut FileSourceMap) -> String {
-    let mut transformed_source = source.to_string();
-
-    for mat in BECH32_REGEX.captures_iter(source).into_iter() {
+/// Replaces bech32 addresses with the `hrp` prefix by their hex form.
+pub fn replace_bech32_addresses(
+    source: &str,
+    hrp: &str,
+    file_source_map: &mut FileSourceMap,
+) -> String {
+    let mut transformed_source = String::with_capacity(source.len());
+    let mut last_end = 0;
+    for mat in BECH32_REGEX.captures_iter(source) {
         let item = mat.get(1).unwrap();
 
         let orig_address = item.as_str();
@@ -47,14 +59,65 @@ pub fn replace_bech32_addresses(source: &str, file_source_map: &mut FileSourceMa
             // libra match, don't replace
             continue;
         }
-        if let Ok(libra_address) = bech32_into_libra(orig_address) {
-            file_source_map.insert_address_layer(
-                item.end(),
+        if let Ok(libra_address) = bech32_into_libra(orig_address, hrp) {
+            transformed_source.push_str(&source[last_end..item.start()]);
+            file_source_map.insert_address_replacement(
+                transformed_source.len(),
                 orig_address.to_owned(),
                 libra_address.clone(),
             );
-            transformed_source = transformed_source.replace(orig_address, &libra_address);
+            transformed_source.push_str(&libra_address);
+            last_end = item.end();
         }
     }
+    transformed_source.push_str(&source[last_end..]);
     transformed_source
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "wallet1me0cdn52672y7feddy7tgcj6j4dkzq2mveywu4";
+    const LIBRA_ADDRESS: &str = "0xde5f86ce8ad7944f272d693cb4625a955b61015b";
+
+    #[test]
+    fn convert_between_bech32_and_libra() {
+        assert_eq!(bech32_into_libra(ADDRESS, "wallet").unwrap(), LIBRA_ADDRESS);
+        assert_eq!(libra_into_bech32(LIBRA_ADDRESS, "wallet").unwrap(), ADDRESS);
+        assert_eq!(
+            libra_into_bech32(LIBRA_ADDRESS, "dfi").unwrap(),
+            "dfi1me0cdn52672y7feddy7tgcj6j4dkzq2mgd7n6t"
+        );
+    }
+
+    #[test]
+    fn address_with_another_prefix_is_invalid() {
+        assert!(bech32_into_libra(ADDRESS, "dfi").is_err());
+    }
+
+    #[test]
+    fn replace_addresses_with_configured_prefix_only() {
+        let source = format!(
+            "use {}::Debug; let _ = dfi1me0cdn52672y7feddy7tgcj6j4dkzq2mgd7n6t; let _ = {};",
+            ADDRESS, ADDRESS
+        );
+        let mut source_map = FileSourceMap::default();
+        let replaced = replace_bech32_addresses(&source, "wallet", &mut source_map);
+        assert_eq!(
+            replaced,
+            format!(
+                "use {}::Debug; let _ = dfi1me0cdn52672y7feddy7tgcj6j4dkzq2mgd7n6t; let _ = {};",
+                LIBRA_ADDRESS, LIBRA_ADDRESS
+            )
+        );
+
+        let start = replaced.rfind(LIBRA_ADDRESS).unwrap();
+        let end = start + LIBRA_ADDRESS.len();
+        let orig_start = source.rfind(ADDRESS).unwrap();
+        assert_eq!(
+            source_map.translate_span((start, end)),
+            (orig_start, orig_start + ADDRESS.len())
+        );
+    }
+}
@@ -3,51 +3,65 @@ use std::collections::HashMap;
 use serde::export::Formatter;
 use std::fmt;
 
-use utils::MoveFilePath;
-
-pub fn len_difference(orig: &str, replacement: &str) -> isize {
-    orig.len() as isize - replacement.len() as isize
-}
+use utils::FileId;
 
+/// Part of the source, replaced before compilation. `start` is the same in both texts.
 #[derive(Debug, Clone)]
-pub struct Layer {
-    pub pos: usize,
-    pub offset: isize,
+pub struct Replacement {
+    pub start: usize,
+    pub original_len: usize,
+    pub replacement_len: usize,
 }
 
-impl Layer {
-    pub fn translate(&self, pos: usize) -> usize {
-        if pos < self.pos {
+impl Replacement {
+    /// Position in the text before the replacement. Positions inside of the replaced part
+    /// are clamped to its end.
+    pub fn original_pos(&self, pos: usize) -> usize {
+        self.map_pos(pos, self.replacement_len, self.original_len)
+    }
+
+    /// Position in the text after the replacement. Positions inside of the replaced part
+    /// are clamped to its end.
+    pub fn transformed_pos(&self, pos: usize) -> usize {
+        self.map_pos(pos, self.original_len, self.replacement_len)
+    }
+
+    fn map_pos(&self, pos: usize, from_len: usize, to_len: usize) -> usize {
+        if pos < self.start {
             pos
+        } else if pos >= self.start + from_len {
+            pos - from_len + to_len
         } else {
-            (pos as isize + self.offset) as usize
+            self.start + usize::min(pos - self.start, to_len)
         }
     }
 }
 
+/// Replacements of the file in the order they were applied, each one with positions in the text
+/// right after it.
 #[derive(Debug, Clone, Default)]
 pub struct FileSourceMap {
-    layers: Vec<Layer>,
+    replacements: Vec<Replacement>,
     address_replacements: Vec<(String, String)>,
 }
 
 impl FileSourceMap {
-    pub fn insert_layer(&mut self, pos: usize, offset: isize) {
-        self.layers.push(Layer { pos, offset });
+    pub fn insert_replacement(&mut self, start: usize, original: &str, replacement: &str) {
+        self.replacements.push(Replacement {
+            start,
+            original_len: original.len(),
+            replacement_len: replacement.len(),
+        });
     }
 
-    pub fn insert_address_layer(
+    /// Replacement of the address literal, also reverted in the error messages.
+    pub fn insert_address_replacement(
         &mut self,
-        original_end_pos: usize,
+        start: usize,
         original: String,
         replacement: String,
     ) {
-        let len_diff = len_difference(&original, &replacement);
-        self.insert_layer(original_end_pos, len_diff);
-        self.insert_address_replacement(original, replacement);
-    }
-
-    pub fn insert_address_replacement(&mut self, original: String, replacement: String) {
+        self.insert_replacement(start, &original, &replacement);
         self.address_replacements.push((original, replacement))
     }
 
@@ -71,18 +85,31 @@ impl FileSourceMap {
         }
     }
 
+    /// Span in the original text from the span in the compiled one.
     pub fn translate_span(&self, (start, end): (usize, usize)) -> (usize, usize) {
         let start = self.translate_pos(start);
         let end = self.translate_pos(end);
         (start, end)
     }
 
-    fn translate_pos(&self, pos: usize) -> usize {
-        let mut real_pos = pos;
-        for layer in self.layers.iter().rev() {
-            real_pos = layer.translate(real_pos);
-        }
-        real_pos
+    /// Position in the original text from the position in the compiled one.
+    pub fn translate_pos(&self, pos: usize) -> usize {
+        self.replacements
+            .iter()
+            .rev()
+            .fold(pos, |pos, replacement| replacement.original_pos(pos))
+    }
+
+    /// Span in the compiled text from the span in the original one.
+    pub fn transformed_span(&self, (start, end): (usize, usize)) -> (usize, usize) {
+        (self.transformed_pos(start), self.transformed_pos(end))
+    }
+
+    /// Position in the compiled text from the position in the original one, like the cursor.
+    pub fn transformed_pos(&self, pos: usize) -> usize {
+        self.replacements
+            .iter()
+            .fold(pos, |pos, replacement| replacement.transformed_pos(pos))
     }
 
     fn translate_error_message(&self, message: String) -> String {
@@ -95,35 +122,36 @@ impl FileSourceMap {
 }
 
 #[derive(Debug, Default, Clone)]
-pub struct ProjectSourceMap(pub HashMap<MoveFilePath, FileSourceMap>);
+pub struct ProjectSourceMap(pub HashMap<FileId, FileSourceMap>);
 
 impl ProjectSourceMap {
-    pub fn with_file_map(fpath: MoveFilePath, map: FileSourceMap) -> ProjectSourceMap {
+    pub fn with_file_map(fpath: FileId, map: FileSourceMap) -> ProjectSourceMap {
         let mut project_map = ProjectSourceMap::default();
         project_map.0.insert(fpath, map);
         project_map
     }
 
-    pub fn insert(&mut self, fpath: MoveFilePath, map: FileSourceMap) {
+    pub fn insert(&mut self, fpath: FileId, map: FileSourceMap) {
         self.0.insert(fpath, map);
     }
 
     pub fn transform(&self, error: CompilerError) -> CompilerError {
         let mut translated_parts = vec![];
         for error_part in error.parts.into_iter() {
-            let file_source_map = &self.0[error_part.location.fpath];
+            let file_source_map = &self.0[&error_part.location.fpath];
             let new_error_part = file_source_map.translate_error_part(error_part);
             translated_parts.push(new_error_part);
         }
         CompilerError {
             parts: translated_parts,
+            ..error
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Location {
-    pub fpath: MoveFilePath,
+    pub fpath: FileId,
     pub span: (usize, usize),
 }
 
@@ -140,12 +168,40 @@ pub struct CompilerErrorPart {
     pub message: String,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Error
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CompilerError {
+    /// First part is the error itself, others are related information.
     pub parts: Vec<CompilerErrorPart>,
+    pub severity: Severity,
+    /// Stable identifier of the kind of error, like `E0001`.
+    pub code: Option<String>,
+}
+
+impl CompilerError {
+    pub fn new(parts: Vec<CompilerErrorPart>) -> CompilerError {
+        CompilerError {
+            parts,
+            severity: Severity::Error,
+            code: None,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ExecCompilerError(pub Vec<CompilerError>, pub ProjectSourceMap);
 
 impl ExecCompilerError {
@@ -173,3 +229,101 @@ impl fmt::Display for ExecCompilerError {
         write!(f, "{:?}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::replace_address_placeholder;
+    use crate::shared::bech32::replace_bech32_addresses;
+    use proptest::prelude::*;
+
+    const SENDER: &str = "0x0000000000000000000000000a550c18";
+    const BECH32_ADDRESS: &str = "wallet1me0cdn52672y7feddy7tgcj6j4dkzq2mveywu4";
+    const HEX_ADDRESS: &str = "0xde5f86ce8ad7944f272d693cb4625a955b61015b";
+
+    /// Parts of the source text with their compiled form.
+    fn source_part() -> impl Strategy<Value = (String, String)> {
+        prop_oneof![
+            "[a-z ;:()=\n]{0,8}".prop_map(|text| (text.clone(), text)),
+            Just(("{{sender}}".to_string(), SENDER.to_string())),
+            Just((
+                format!(" {};", BECH32_ADDRESS),
+                format!(" {};", HEX_ADDRESS)
+            )),
+        ]
+    }
+
+    fn transform(source: &str) -> (String, FileSourceMap) {
+        let mut source_map = FileSourceMap::default();
+        let text = replace_address_placeholder(
+            source.to_string(),
+            SENDER,
+            &["{{sender}}"],
+            &mut source_map,
+        );
+        let text = replace_bech32_addresses(&text, "wallet", &mut source_map);
+        (text, source_map)
+    }
+
+    /// Positions at the borders of the parts are translated both ways.
+    fn check_round_trip(parts: &[(String, String)]) -> Result<(), TestCaseError> {
+        let source: String = parts
+            .iter()
+            .map(|(original, _)| original.as_str())
+            .collect();
+        let (transformed, source_map) = transform(&source);
+        let expected: String = parts
+            .iter()
+            .map(|(_, compiled)| compiled.as_str())
+            .collect();
+        prop_assert_eq!(&transformed, &expected);
+
+        let (mut original_pos, mut transformed_pos) = (0, 0);
+        for (original, compiled) in parts {
+            prop_assert_eq!(source_map.transformed_pos(original_pos), transformed_pos);
+            prop_assert_eq!(source_map.translate_pos(transformed_pos), original_pos);
+
+            let original_span = (original_pos, original_pos + original.len());
+            let transformed_span = (transformed_pos, transformed_pos + compiled.len());
+            prop_assert_eq!(source_map.transformed_span(original_span), transformed_span);
+            prop_assert_eq!(source_map.translate_span(transformed_span), original_span);
+
+            original_pos += original.len();
+            transformed_pos += compiled.len();
+        }
+        prop_assert_eq!(source_map.transformed_pos(source.len()), transformed.len());
+        prop_assert_eq!(source_map.translate_pos(transformed.len()), source.len());
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn positions_between_substitutions_round_trip(
+            parts in prop::collection::vec(source_part(), 0..10)
+        ) {
+            check_round_trip(&parts)?;
+        }
+    }
+
+    #[test]
+    fn bech32_address_followed_by_semicolon_and_sender_round_trips() {
+        let sender = ("{{sender}}".to_string(), SENDER.to_string());
+        let parts = vec![
+            (
+                format!(" {};", BECH32_ADDRESS),
+                format!(" {};", HEX_ADDRESS),
+            ),
+            sender.clone(),
+            sender,
+        ];
+        check_round_trip(&parts).unwrap();
+    }
+
+    #[test]
+    fn positions_inside_of_replacement_are_clamped() {
+        let (_, source_map) = transform("let a = {{sender}};");
+        assert_eq!(source_map.transformed_pos(10), 10);
+        assert_eq!(source_map.translate_pos(30), 18);
+        assert_eq!(source_map.transformed_pos(19), 19 + SENDER.len() - 10);
+    }
+}
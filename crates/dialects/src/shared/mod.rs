@@ -1,10 +1,15 @@
+use anyhow::Result;
 use move_core_types::account_address::AccountAddress;
 use move_lang::shared::Address;
+use std::collections::BTreeMap;
 
 pub mod addresses;
 pub mod bech32;
 pub mod errors;
 
+/// Named addresses, substituted for `{{name}}` placeholders in the source.
+pub type AddressPlaceholders = BTreeMap<String, ProvidedAccountAddress>;
+
 #[derive(Debug, Clone)]
 pub struct ProvidedAccountAddress {
     pub original: String,
@@ -21,26 +26,30 @@ impl ProvidedAccountAddress {
         }
     }
 
+    /// Hex form, padded to the address length of the dialect.
     pub fn lowered(&self) -> String {
-        let lowered_bits = self.lowered[2..].to_owned();
-        format!("0x{:0>40}", lowered_bits)
+        self.lowered.clone()
     }
 
-    pub fn as_address(&self) -> Address {
-        Address::new(self.as_account_address().into())
+    pub fn as_address(&self) -> Result<Address> {
+        Ok(Address::new(self.as_account_address()?.into()))
     }
 
-    pub fn as_account_address(&self) -> AccountAddress {
-        AccountAddress::from_hex_literal(&self.lowered).unwrap()
+    /// Fails if the significant digits don't fit into the compiler address.
+    pub fn as_account_address(&self) -> Result<AccountAddress> {
+        // padding of the dialect could be wider than the compiler address
+        let hex_digits = self.lowered[2..].trim_start_matches('0');
+        AccountAddress::from_hex_literal(&format!("0x{:0>1}", hex_digits))
     }
 }
 
 impl Default for ProvidedAccountAddress {
     fn default() -> Self {
+        let zero_address = format!("0x{}", "0".repeat(AccountAddress::LENGTH * 2));
         ProvidedAccountAddress {
             original: "0x0".to_string(),
-            normalized_original: "0x0000000000000000000000000000".to_string(),
-            lowered: "0x000000000000000000000000000000000000".to_string(),
+            normalized_original: zero_address.clone(),
+            lowered: zero_address,
         }
     }
 }
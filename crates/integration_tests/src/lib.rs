@@ -1,16 +1,17 @@
 use analysis::change::AnalysisChange;
 use analysis::config::Config;
+use lsp_types::{Position, TextDocumentIdentifier, TextDocumentPositionParams, Url};
 use move_language_server::global_state::{initialize_new_global_state, GlobalStateSnapshot};
 use std::path::PathBuf;
 use utils::io::read_move_files;
-use utils::{io, leaked_fpath, MoveFile, MoveFilePath};
+use utils::{io, FileId, MoveFile};
 
-pub fn get_script_path() -> MoveFilePath {
-    leaked_fpath(get_modules_path().join("script.move"))
+pub fn get_script_path() -> FileId {
+    FileId::new(get_modules_path().join("script.move"))
 }
 
 // just need some valid fname
-pub fn existing_module_file_abspath() -> MoveFilePath {
+pub fn existing_module_file_abspath() -> FileId {
     let abspath = std::env::current_dir()
         .unwrap()
         .join("resources")
@@ -19,7 +20,7 @@ pub fn existing_module_file_abspath() -> MoveFilePath {
         .into_os_string()
         .into_string()
         .unwrap();
-    leaked_fpath(&abspath)
+    FileId::new(&abspath)
 }
 
 pub fn get_test_resources_dir() -> PathBuf {
@@ -75,6 +76,12 @@ pub fn global_state_snapshot(
     global_state.snapshot()
 }
 
+pub fn position_params(pos: (u64, u64), fpath: FileId) -> TextDocumentPositionParams {
+    let text_document = TextDocumentIdentifier::new(Url::from_file_path(fpath).unwrap());
+    let position = Position::new(pos.0, pos.1);
+    TextDocumentPositionParams::new(text_document, position)
+}
+
 #[macro_export]
 macro_rules! config {
     () => {{
@@ -1,6 +1,6 @@
-use lsp_types::{Diagnostic, Position, Range};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url};
 
-use analysis::analysis::Analysis;
+use analysis::analysis::{Analysis, AnalysisHost};
 use analysis::change::AnalysisChange;
 use analysis::config::Config;
 use analysis::db::FileDiagnostic;
@@ -9,7 +9,7 @@ use move_language_server::global_state::initialize_new_global_state;
 
 use crossbeam_channel::unbounded;
 use move_language_server::main_loop::{compute_file_diagnostics, Task};
-use utils::{leaked_fpath, MoveFile, MoveFilePath};
+use utils::{FileId, MoveFile};
 
 fn range(start: (u64, u64), end: (u64, u64)) -> Range {
     Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
@@ -23,28 +23,30 @@ fn diagnostics_with_config(text: &str, config: Config) -> Vec<Diagnostic> {
     let loc_ds = diagnostics_with_config_and_filename(
         text,
         config,
-        leaked_fpath(get_test_resources_dir().join("some_script.move")),
+        FileId::new(get_test_resources_dir().join("some_script.move")),
     );
-    loc_ds.into_iter().filter_map(|d| d.diagnostic).collect()
+    loc_ds.into_iter().flat_map(|d| d.diagnostics).collect()
 }
 
 fn diagnostics_with_config_and_filename(
     text: &str,
     config: Config,
-    fpath: MoveFilePath,
+    fpath: FileId,
 ) -> Vec<FileDiagnostic> {
     let state_snapshot = global_state_snapshot((fpath, text.to_string()), config, vec![]);
     let (task_sender, task_receiver) = unbounded::<Task>();
 
     compute_file_diagnostics(state_snapshot.analysis, task_sender, vec![fpath]);
 
-    let task = task_receiver.try_recv().unwrap();
+    // first task holds syntax errors only, compiler results come last
+    let task = task_receiver.try_iter().last().unwrap();
     let mut ds = match task {
-        Task::Diagnostic(ds) => ds,
+        Task::Diagnostic(_, _, ds) => ds,
         _ => panic!(),
     };
-    let empty = ds.remove(0);
-    assert!(empty.diagnostic.is_none());
+    // checked file always comes first, to clear its previous diagnostics
+    assert_eq!(ds[0].fpath, fpath);
+    ds.retain(|d| !d.diagnostics.is_empty());
     ds
 }
 
@@ -52,7 +54,7 @@ fn diagnostics_with_deps(
     script_file: MoveFile,
     deps: Vec<MoveFile>,
     config: Config,
-) -> Option<FileDiagnostic> {
+) -> Vec<FileDiagnostic> {
     let (script_fpath, script_text) = script_file;
     let mut config = config;
     config.update(&serde_json::json!({
@@ -79,11 +81,14 @@ fn diagnostics_with_deps(
 mod tests {
     use super::*;
     use analysis::db::RootDatabase;
+    use dialects::registry::{register_dialect, AddressFormat, DialectSpec};
+    use dialects::shared::errors::{CompilerError, CompilerErrorPart, Location, Severity};
 
     use integration_tests::{
         config, get_modules_path, get_script_path, get_stdlib_path, modules_mod,
     };
-    use utils::{leaked_fpath, FilesSourceText};
+    use std::sync::Arc;
+    use utils::FilesSourceText;
 
     #[test]
     fn test_fail_on_non_ascii_character() {
@@ -112,6 +117,99 @@ script {
 
         assert_eq!(errors[0].message, "Unexpected 'struc'");
         assert_eq!(errors[0].range, range((0, 11), (0, 16)));
+        assert_eq!(errors[0].severity, Some(DiagnosticSeverity::Error));
+        assert_eq!(errors[0].code, None);
+    }
+
+    #[test]
+    fn test_compiler_warning_with_code_into_diagnostic() {
+        let fpath = get_script_path();
+        let mut files = FilesSourceText::new();
+        files.insert(fpath, "script { fun main() {} }".to_string());
+        let db = RootDatabase::new(Config::default(), files);
+
+        let mut warning = CompilerError::new(vec![CompilerErrorPart {
+            location: Location {
+                fpath,
+                span: (13, 17),
+            },
+            message: "Unused function".to_string(),
+        }]);
+        warning.severity = Severity::Warning;
+        warning.code = Some("W0001".to_string());
+
+        let file_diagnostic = db.compiler_error_into_diagnostic(warning).unwrap();
+        let diagnostic = &file_diagnostic.diagnostics[0];
+        assert_eq!(diagnostic.range, range((0, 13), (0, 17)));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Warning));
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("W0001".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_error_located_in_stdlib_file() {
+        let signer_fpath = FileId::new(get_stdlib_path().join("signer.move"));
+        let unknown_fpath = FileId::new(get_stdlib_path().join("unknown.move"));
+        let config = config!({ "stdlib_folder": get_stdlib_path() });
+        let db = RootDatabase::new(config, FilesSourceText::new());
+
+        let error = CompilerError::new(vec![
+            CompilerErrorPart {
+                location: Location {
+                    fpath: signer_fpath,
+                    span: (0, 7),
+                },
+                message: "Invalid address".to_string(),
+            },
+            CompilerErrorPart {
+                location: Location {
+                    fpath: unknown_fpath,
+                    span: (0, 1),
+                },
+                message: "Declared here".to_string(),
+            },
+        ]);
+        let file_diagnostic = db.compiler_error_into_diagnostic(error).unwrap();
+        assert_eq!(file_diagnostic.fpath, signer_fpath);
+        let diagnostic = &file_diagnostic.diagnostics[0];
+        assert_eq!(diagnostic.range, range((0, 0), (0, 7)));
+        assert_eq!(diagnostic.related_information, Some(vec![]));
+
+        let error = CompilerError::new(vec![CompilerErrorPart {
+            location: Location {
+                fpath: unknown_fpath,
+                span: (0, 1),
+            },
+            message: "Invalid address".to_string(),
+        }]);
+        assert!(db.compiler_error_into_diagnostic(error).is_err());
+    }
+
+    #[test]
+    fn test_related_information_in_stdlib_file() {
+        let source_text = r"
+script {
+    use 0x1::Signer;
+
+    fun main() {
+        Signer::address_of(1);
+    }
+}
+";
+        let config = config!({ "stdlib_folder": get_stdlib_path() });
+        let errors = diagnostics_with_config(source_text, config);
+        assert_eq!(errors.len(), 1, "{:#?}", errors);
+        let signer_uri = Url::from_file_path(get_stdlib_path().join("signer.move")).unwrap();
+        let related_information = errors[0].related_information.as_ref().unwrap();
+        assert!(
+            related_information
+                .iter()
+                .any(|info| info.location.uri == signer_uri),
+            "{:#?}",
+            related_information
+        );
     }
 
     #[test]
@@ -300,17 +398,94 @@ script {
         assert_eq!(errors[0].related_information.as_ref().unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_parsed_dependency_is_invalidated_on_change() {
+        let config = config!({ "modules_folders": [get_modules_path()] });
+        let dep_module_fpath = FileId::new(get_modules_path().join("dep_module.move"));
+        let main_fpath = FileId::new(get_modules_path().join("module.move"));
+        let source_text = r"
+    module HowMany {
+        use 0x0::T;
+        public fun how() {
+            T::how_many()
+        }
+    }
+    ";
+        let mut analysis_host = AnalysisHost::default();
+        let mut change = AnalysisChange::new();
+        change.change_config(config);
+        change.add_file(
+            dep_module_fpath,
+            "address 0x0 { modules T { public fun how_many() {} } }".to_string(),
+        );
+        change.add_file(main_fpath, source_text.to_string());
+        analysis_host.apply_change(change);
+
+        for _ in 0..2 {
+            let errors = analysis_host
+                .analysis()
+                .check_file_with_compiler(main_fpath, source_text);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].fpath, dep_module_fpath);
+        }
+
+        let mut change = AnalysisChange::new();
+        change.update_file(
+            dep_module_fpath,
+            "address 0x0 { module T { public fun how_many() {} } }".to_string(),
+        );
+        analysis_host.apply_change(change);
+        let errors = analysis_host
+            .analysis()
+            .check_file_with_compiler(main_fpath, source_text);
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    #[test]
+    fn test_dependencies_are_collected_once_per_file_set() {
+        let config = config!({
+            "stdlib_folder": get_stdlib_path(),
+            "modules_folders": [get_modules_path()]
+        });
+        let module_fpath = FileId::new(get_modules_path().join("module.move"));
+        let mut analysis_host = AnalysisHost::default();
+        let mut change = AnalysisChange::new();
+        change.change_config(config);
+        change.add_file(module_fpath, "module M {}".to_string());
+        analysis_host.apply_change(change);
+
+        let deps = analysis_host.db().dependency_fpaths();
+        assert!(deps.contains(&module_fpath));
+        assert!(deps
+            .iter()
+            .any(|fpath| analysis_host.db().is_stdlib_file(*fpath)));
+
+        let mut change = AnalysisChange::new();
+        change.update_file(module_fpath, "module M { fun f() {} }".to_string());
+        analysis_host.apply_change(change);
+        assert!(Arc::ptr_eq(&deps, &analysis_host.db().dependency_fpaths()));
+
+        let dep_module_fpath = FileId::new(get_modules_path().join("dep_module.move"));
+        let mut change = AnalysisChange::new();
+        change.add_file(dep_module_fpath, "module T {}".to_string());
+        analysis_host.apply_change(change);
+        assert!(analysis_host
+            .db()
+            .dependency_fpaths()
+            .contains(&dep_module_fpath));
+    }
+
     #[test]
     fn test_syntax_error_in_dependency() {
         let config = config!({ "modules_folders": [get_modules_path()] });
 
         let mut files = FilesSourceText::new();
         let dep_module_fpath =
-            leaked_fpath(get_modules_path().join("dep_module.move").to_str().unwrap());
+            FileId::new(get_modules_path().join("dep_module.move").to_str().unwrap());
         let dep_module_source_text = "address 0x0 { modules T { public fun how_many() {} } }";
         files.insert(dep_module_fpath, dep_module_source_text.to_string());
 
-        let main_fpath = leaked_fpath(get_modules_path().join("module.move").to_str().unwrap());
+        let main_fpath = FileId::new(get_modules_path().join("module.move").to_str().unwrap());
         let source_text = r"
     module HowMany {
         use 0x0::T;
@@ -321,17 +496,77 @@ script {
     ";
         files.insert(main_fpath, source_text.to_string());
 
-        let db = RootDatabase {
-            config,
-            available_files: files,
-        };
+        let db = RootDatabase::new(config, files);
         let analysis = Analysis::new(db);
-        let error = analysis
-            .check_file_with_compiler(main_fpath, source_text)
+        let errors = analysis.check_file_with_compiler(main_fpath, source_text);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].fpath, dep_module_fpath);
+        assert_eq!(errors[0].diagnostics.len(), 1);
+        assert_eq!(errors[0].diagnostics[0].message, "Unexpected 'modules'");
+    }
+
+    #[test]
+    fn test_all_errors_of_the_file_are_reported() {
+        let source_text = r"
+module M {
+    struct S { f: u64, f: u64 }
+    struct T { g: u64, g: u64 }
+}
+    ";
+        let errors = diagnostics(source_text);
+        assert_eq!(errors.len(), 2, "{:#?}", errors);
+        assert_eq!(
+            errors[0].message,
+            "Duplicate definition for field \'f\' in struct \'S\'"
+        );
+        assert_eq!(
+            errors[1].message,
+            "Duplicate definition for field \'g\' in struct \'T\'"
+        );
+    }
+
+    #[test]
+    fn test_errors_are_grouped_per_file_including_dependencies() {
+        let config = config!({ "modules_folders": [get_modules_path()] });
+
+        let mut files = FilesSourceText::new();
+        let dep_module_fpath =
+            FileId::new(get_modules_path().join("dep_module.move").to_str().unwrap());
+        let dep_module_source_text = "address 0x0 { modules T { public fun how_many() {} } }";
+        files.insert(dep_module_fpath, dep_module_source_text.to_string());
+
+        let main_fpath = FileId::new(get_modules_path().join("module.move").to_str().unwrap());
+        let source_text = "module HowMany { struc S {} }";
+        files.insert(main_fpath, source_text.to_string());
+
+        let db = RootDatabase::new(config, files);
+        let (task_sender, task_receiver) = unbounded::<Task>();
+        compute_file_diagnostics(
+            Analysis::new(db),
+            task_sender,
+            vec![main_fpath, dep_module_fpath],
+        );
+        // compiler results of the file come after its syntax errors
+        let file_diagnostics = task_receiver
+            .try_iter()
+            .filter_map(|task| match task {
+                Task::Diagnostic(_, checked, ds) if checked == main_fpath => Some(ds),
+                _ => None,
+            })
+            .last()
             .unwrap();
-        assert_eq!(error.fpath, dep_module_fpath);
+        // one entry per file, dependency errors are not duplicated
+        assert_eq!(file_diagnostics.len(), 2, "{:#?}", file_diagnostics);
+        assert_eq!(file_diagnostics[0].fpath, main_fpath);
+        assert_eq!(file_diagnostics[0].diagnostics.len(), 1);
         assert_eq!(
-            error.diagnostic.as_ref().unwrap().message,
+            file_diagnostics[0].diagnostics[0].message,
+            "Unexpected 'struc'"
+        );
+        assert_eq!(file_diagnostics[1].fpath, dep_module_fpath);
+        assert_eq!(file_diagnostics[1].diagnostics.len(), 1);
+        assert_eq!(
+            file_diagnostics[1].diagnostics[0].message,
             "Unexpected 'modules'"
         );
     }
@@ -353,7 +588,7 @@ address 0x1 {
         let errors = diagnostics_with_config_and_filename(
             source_text,
             config,
-            leaked_fpath(get_stdlib_path().join("debug.move")),
+            FileId::new(get_stdlib_path().join("debug.move")),
         );
         assert!(errors.is_empty(), "{:?}", errors);
     }
@@ -421,6 +656,25 @@ address 0x12345 {
         assert_eq!(errors[0].range, range((6, 19), (6, 22)));
     }
 
+    #[test]
+    fn test_starcoin_address_longer_than_16_bytes_is_reported() {
+        let source_text = r"
+script {
+    fun main() {
+        let _ = 0x000000000000000011111111111111111111;
+    }
+}
+    ";
+        let errors = diagnostics_with_config(source_text, config!({"dialect": "starcoin"}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "Address 0x000000000000000011111111111111111111 is too long, \
+             addresses should be at most 16 bytes long"
+        );
+        assert_eq!(errors[0].range, range((3, 16), (3, 54)));
+    }
+
     #[test]
     fn pass_bech32_address_as_sender() {
         let source_text = r"
@@ -576,12 +830,12 @@ script {
         let error = diagnostics_with_deps(
             (get_script_path(), source_text.to_string()),
             vec![(
-                leaked_fpath(get_modules_path().join("debug.move")),
+                FileId::new(get_modules_path().join("debug.move")),
                 module_text.to_string(),
             )],
             config,
         );
-        assert!(error.is_none(), "{:#?}", error);
+        assert!(error.is_empty(), "{:#?}", error);
     }
 
     #[test]
@@ -618,6 +872,221 @@ script {
         assert_eq!(errors[0].message, "Unbound module \'0x12345::Unknown\'")
     }
 
+    #[test]
+    fn test_dialect_specific_sender_normalization() {
+        let config = config!({
+            "dialect": "starcoin",
+            "sender_address": "0x12345"
+        });
+        assert_eq!(config.dialect().name(), "starcoin");
+        assert_eq!(
+            config.sender().normalized_original,
+            "0x00000000000000000000000000012345"
+        );
+
+        // 20 bytes address doesn't fit, dialect's default sender is used instead
+        let config = config!({
+            "dialect": "starcoin",
+            "sender_address": "0x0000000000000000000000001111111111111111"
+        });
+        assert_eq!(config.sender().original, "0xa550c18");
+
+        let config = config!({ "dialect": "libra" });
+        assert_eq!(config.dialect().name(), "libra");
+        assert_eq!(config.sender().original, "0x1");
+    }
+
+    #[test]
+    fn test_sender_is_padded_to_address_length_of_dialect() {
+        for (dialect, address_length) in &[("starcoin", 16), ("dfinance", 20)] {
+            let config = config!({ "dialect": dialect, "sender_address": "0x42" });
+            assert_eq!(config.dialect().address_length(), *address_length);
+            assert_eq!(
+                config.sender().lowered(),
+                format!("0x{:0>width$}", 42, width = address_length * 2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_named_address_placeholders_from_config() {
+        let config = config!({
+            "address_placeholders": {
+                "std": "0x1",
+                "treasury": "0x42",
+                "invalid": "0xXYZ",
+                "sender": "0x3",
+            }
+        });
+        let names: Vec<_> = config.address_placeholders.keys().collect();
+        assert_eq!(names, vec!["std", "treasury"]);
+
+        let source_text = r"
+script {
+    use {{std}}::Signer;
+    fun main() {
+        let _ = {{ treasury }}::Unknown::unknown();
+    }
+}
+        ";
+        let errors = diagnostics_with_config(source_text, config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unbound module '0x42::Unknown'");
+        assert_eq!(errors[0].range, range((4, 16), (4, 48)));
+    }
+
+    #[test]
+    fn test_short_addresses_are_reported_in_original_form_in_both_dialects() {
+        let source_text = r"
+script {
+    fun main() {
+        let _ = 0x2::Unknown::unknown();
+    }
+}
+        ";
+        for dialect in &["libra", "starcoin"] {
+            let errors = diagnostics_with_config(source_text, config!({ "dialect": dialect }));
+            assert_eq!(errors.len(), 1, "{}: {:#?}", dialect, errors);
+            assert_eq!(errors[0].message, "Unbound module '0x2::Unknown'");
+            assert_eq!(errors[0].range, range((3, 16), (3, 28)));
+        }
+    }
+
+    #[test]
+    fn test_starcoin_modules_at_short_and_full_addresses_are_the_same() {
+        let module_text = r"
+address 0x2 {
+    module Debug {
+        public fun debug(): u8 {
+            1
+        }
+    }
+}";
+        let source_text = r"
+script {
+    fun main() {
+        let _ = 0x00000000000000000000000000000002::Debug::debug();
+    }
+}
+        ";
+        let errors = diagnostics_with_deps(
+            (get_script_path(), source_text.to_string()),
+            vec![(
+                FileId::new(get_modules_path().join("debug.move")),
+                module_text.to_string(),
+            )],
+            config!({ "dialect": "starcoin" }),
+        );
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    const DFI_ADDRESS: &str = "dfi1me0cdn52672y7feddy7tgcj6j4dkzq2mgd7n6t";
+
+    fn dfinance_config() -> Config {
+        config!({
+            "dialect": "dfinance",
+            "bech32_hrp": "dfi",
+            "sender_address": DFI_ADDRESS,
+        })
+    }
+
+    #[test]
+    fn test_dfinance_sender_with_configured_prefix() {
+        let config = dfinance_config();
+        assert_eq!(config.dialect().name(), "dfinance");
+        assert_eq!(config.sender().original, DFI_ADDRESS);
+        assert_eq!(
+            config.sender().lowered(),
+            "0xde5f86ce8ad7944f272d693cb4625a955b61015b"
+        );
+
+        // prefix doesn't match the configured one
+        let config = config!({
+            "dialect": "dfinance",
+            "bech32_hrp": "dfi",
+            "sender_address": "wallet1me0cdn52672y7feddy7tgcj6j4dkzq2mveywu4",
+        });
+        assert_eq!(config.sender().original, "0x1");
+    }
+
+    #[test]
+    fn test_dfinance_errors_after_bech32_address() {
+        let source_text = format!(
+            r"
+address {} {{
+    module Debug {{
+        pubic fun main() {{}}
+    }}
+}}
+    ",
+            DFI_ADDRESS
+        );
+        let errors = diagnostics_with_config(&source_text, dfinance_config());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unexpected 'pubic'");
+        assert_eq!(errors[0].range, range((3, 8), (3, 13)));
+    }
+
+    #[test]
+    fn test_dfinance_error_message_with_bech32_address_and_sender() {
+        let source_text = format!(
+            r"
+script {{
+    fun main() {{
+        let _ = {{{{sender}}}};
+        let _ = {}::Unknown::unknown();
+    }}
+}}
+    ",
+            DFI_ADDRESS
+        );
+        let errors = diagnostics_with_config(&source_text, dfinance_config());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            format!("Unbound module '{}::Unknown'", DFI_ADDRESS)
+        );
+        assert_eq!(errors[0].range, range((4, 16), (4, 76)));
+    }
+
+    #[test]
+    fn test_dialect_registered_outside_of_dialects_crate() {
+        register_dialect(DialectSpec {
+            name: "testnet",
+            address_length: 16,
+            address_formats: &[AddressFormat::Hex { widen: true }],
+            sender_placeholders: &["{{me}}"],
+            builtins: &["exists"],
+            default_sender: "0x42",
+        })
+        .unwrap();
+        let config = config!({ "dialect": "testnet" });
+        assert_eq!(config.dialect().name(), "testnet");
+        assert_eq!(config.dialect().builtins(), &["exists"]);
+        assert_eq!(
+            config.sender().normalized_original,
+            "0x00000000000000000000000000000042"
+        );
+
+        let source_text = r"
+script {
+    fun main() {
+        let _ = {{me}}::Unknown::unknown();
+    }
+}
+        ";
+        let errors = diagnostics_with_config(source_text, config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unbound module '0x42::Unknown'");
+        assert_eq!(errors[0].range, range((3, 16), (3, 40)));
+    }
+
+    #[test]
+    fn test_unknown_dialect_is_ignored() {
+        let config = config!({ "dialect": "unknown" });
+        assert_eq!(config.dialect().name(), "libra");
+    }
+
     #[test]
     fn test_starcoin_documentation_issue_should_not_crash_with_span_overflow() {
         let dfi_module_text = r"
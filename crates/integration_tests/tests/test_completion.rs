@@ -1,18 +1,10 @@
 use analysis::config::Config;
-use integration_tests::global_state_snapshot;
-use lsp_types::{
-    CompletionItem, Position, TextDocumentIdentifier, TextDocumentPositionParams, Url,
-};
+use integration_tests::{global_state_snapshot, position_params};
+use lsp_types::CompletionItem;
 use move_language_server::handlers::handle_completion;
 use move_language_server::req;
 
-use utils::{MoveFile, MoveFilePath};
-
-fn position_params(pos: (u64, u64), fpath: MoveFilePath) -> req::TextDocumentPositionParams {
-    let text_document = TextDocumentIdentifier::new(Url::from_file_path(fpath).unwrap());
-    let position = Position::new(pos.0, pos.1);
-    TextDocumentPositionParams::new(text_document, position)
-}
+use utils::MoveFile;
 
 fn completions(file: MoveFile, config: Config, pos: (u64, u64)) -> Vec<CompletionItem> {
     let snapshot = global_state_snapshot(file.clone(), config, vec![file.clone()]);
@@ -31,8 +23,18 @@ fn completions(file: MoveFile, config: Config, pos: (u64, u64)) -> Vec<Completio
 
 #[cfg(test)]
 mod tests {
-    use crate::completions;
-    use integration_tests::{config, get_script_path};
+    use super::*;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+    use utils::FileId;
+
+    fn labels_and_details(items: Vec<CompletionItem>) -> Vec<(String, String)> {
+        let mut items: Vec<_> = items
+            .into_iter()
+            .map(|item| (item.label, item.detail.unwrap_or_default()))
+            .collect();
+        items.sort();
+        items
+    }
 
     #[test]
     fn test_top_level_completion() {
@@ -43,4 +45,134 @@ mod tests {
             (0, 0),
         );
     }
+
+    #[test]
+    fn test_completion_of_stdlib_module_members() {
+        let source_text = r"
+script {
+    use 0x1::Signer;
+
+    fun main(s: &signer) {
+        let addr = Signer::
+    }
+}
+";
+        let items = completions(
+            (get_script_path(), source_text.to_string()),
+            config!({ "stdlib_folder": get_stdlib_path() }),
+            (5, 27),
+        );
+        assert_eq!(
+            labels_and_details(items),
+            vec![
+                (
+                    "address_of".to_string(),
+                    "public fun address_of(s: &signer): address".to_string()
+                ),
+                (
+                    "borrow_address".to_string(),
+                    "native public fun borrow_address(s: &signer): &address".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_completion_in_untracked_stdlib_file() {
+        let vector_fpath = FileId::new(get_stdlib_path().join("vector.move"));
+        let snapshot = global_state_snapshot(
+            (get_script_path(), "script {}".to_string()),
+            config!({ "stdlib_folder": get_stdlib_path() }),
+            vec![],
+        );
+        let comp_params = req::CompletionParams {
+            text_document_position: position_params((5, 4), vector_fpath),
+            work_done_progress_params: req::WorkDoneProgressParams::default(),
+            partial_result_params: req::PartialResultParams::default(),
+            context: None,
+        };
+        let items = match handle_completion(snapshot, comp_params).unwrap().unwrap() {
+            req::CompletionResponse::Array(items) => items,
+            req::CompletionResponse::List(_) => unreachable!(),
+        };
+        assert!(items.iter().any(|item| item.label == "fun "));
+    }
+
+    #[test]
+    fn test_completion_of_module_members_at_explicit_address() {
+        let source_text = r"
+script {
+    fun main(s: &signer) {
+        0x2::Record::cre
+    }
+}
+";
+        let items = completions(
+            (get_script_path(), source_text.to_string()),
+            config!({ "modules_folders": [get_modules_path()] }),
+            (3, 24),
+        );
+        let labels: Vec<_> = labels_and_details(items)
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "T",
+                "create",
+                "destroy_record",
+                "get_record",
+                "save",
+                "with_doubled_age"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_private_functions_are_completed_only_inside_their_module() {
+        let source_text = r"
+address 0x2 {
+module Counter {
+    struct Counter { value: u64 }
+
+    fun zero(): Counter { Counter { value: 0 } }
+
+    public fun one(): Counter {
+        Self::
+    }
+}
+}
+script {
+    fun main() {
+        0x2::Counter::
+    }
+}
+";
+        let file = (get_script_path(), source_text.to_string());
+        let items = completions(file.clone(), config!({}), (8, 14));
+        assert_eq!(
+            labels_and_details(items),
+            vec![
+                (
+                    "Counter".to_string(),
+                    "struct Counter {\n    value: u64,\n}".to_string()
+                ),
+                ("one".to_string(), "public fun one(): Counter".to_string()),
+                ("zero".to_string(), "fun zero(): Counter".to_string()),
+            ]
+        );
+
+        let items = completions(file, config!({}), (14, 22));
+        assert_eq!(
+            labels_and_details(items),
+            vec![
+                (
+                    "Counter".to_string(),
+                    "struct Counter {\n    value: u64,\n}".to_string()
+                ),
+                ("one".to_string(), "public fun one(): Counter".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,98 @@
+use analysis::analysis::AnalysisHost;
+use analysis::change::AnalysisChange;
+use utils::{FileId, MoveFile};
+
+fn dependent_files(files: Vec<MoveFile>, changed: &[FileId]) -> Vec<FileId> {
+    let mut analysis_host = AnalysisHost::default();
+    let mut change = AnalysisChange::new();
+    for (fpath, text) in files {
+        change.add_file(fpath, text);
+    }
+    analysis_host.apply_change(change);
+
+    let mut dependents = analysis_host.analysis().dependent_files(changed);
+    dependents.sort();
+    dependents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{get_modules_path, get_script_path};
+
+    fn module_fpath(name: &str) -> FileId {
+        FileId::new(get_modules_path().join(name))
+    }
+
+    fn files() -> Vec<MoveFile> {
+        vec![
+            (
+                module_fpath("record.move"),
+                "address 0x1 { module Record { public fun create() {} } }".to_string(),
+            ),
+            (
+                module_fpath("registry.move"),
+                r"
+address 0x1 {
+    module Registry {
+        use 0x1::Record;
+
+        public fun register() { Record::create() }
+    }
+}
+"
+                .to_string(),
+            ),
+            (
+                get_script_path(),
+                "script { fun main() { 0x1::Registry::register() } }".to_string(),
+            ),
+            (
+                module_fpath("coin.move"),
+                "address 0x1 { module Coin {} }".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_dependents_of_module_are_found_transitively() {
+        let dependents = dependent_files(files(), &[module_fpath("record.move")]);
+        let mut expected = vec![
+            module_fpath("record.move"),
+            module_fpath("registry.move"),
+            get_script_path(),
+        ];
+        expected.sort();
+        assert_eq!(dependents, expected);
+    }
+
+    #[test]
+    fn test_script_has_no_dependents() {
+        let dependents = dependent_files(files(), &[get_script_path()]);
+        assert_eq!(dependents, vec![get_script_path()]);
+    }
+
+    #[test]
+    fn test_unused_module_has_no_dependents() {
+        let dependents = dependent_files(files(), &[module_fpath("coin.move")]);
+        assert_eq!(dependents, vec![module_fpath("coin.move")]);
+    }
+
+    #[test]
+    fn test_module_address_is_matched_in_any_form() {
+        let files = vec![
+            (
+                module_fpath("coin.move"),
+                "address 0x1 { module Coin {} }".to_string(),
+            ),
+            (
+                get_script_path(),
+                "script { use 0x0001::Coin; fun main() {} }".to_string(),
+            ),
+        ];
+        let dependents = dependent_files(files, &[module_fpath("coin.move")]);
+        let mut expected = vec![module_fpath("coin.move"), get_script_path()];
+        expected.sort();
+        assert_eq!(dependents, expected);
+    }
+}
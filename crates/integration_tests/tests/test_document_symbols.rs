@@ -0,0 +1,94 @@
+use analysis::config::Config;
+use integration_tests::global_state_snapshot;
+use lsp_types::{DocumentSymbol, SymbolKind, TextDocumentIdentifier, Url};
+use move_language_server::handlers::handle_document_symbol;
+use move_language_server::req;
+
+use utils::MoveFile;
+
+fn document_symbols(file: MoveFile, config: Config) -> Vec<DocumentSymbol> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(Url::from_file_path(file.0).unwrap()),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+        partial_result_params: req::PartialResultParams::default(),
+    };
+    match handle_document_symbol(snapshot, params).unwrap().unwrap() {
+        req::DocumentSymbolResponse::Nested(symbols) => symbols,
+        _ => unreachable!(),
+    }
+}
+
+/// Symbol tree as lines of `name: kind`, indented by the nesting level.
+fn outline(symbols: &[DocumentSymbol], depth: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for symbol in symbols {
+        lines.push(format!(
+            "{}{}: {:?}",
+            "  ".repeat(depth),
+            symbol.name,
+            symbol.kind
+        ));
+        lines.extend(outline(
+            symbol.children.as_deref().unwrap_or_default(),
+            depth + 1,
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{config, get_script_path, modules_mod};
+
+    #[test]
+    fn test_document_symbols_of_address_block() {
+        let symbols = document_symbols(modules_mod("record.move"), config!());
+        assert_eq!(
+            outline(&symbols, 0),
+            vec![
+                "0x2: Namespace",
+                "  Record: Module",
+                "    T: Struct",
+                "      age: Field",
+                "    get_record: Function",
+                "    create: Function",
+                "    save: Function",
+                "    destroy_record: Function",
+                "    with_doubled_age: Function",
+            ]
+        );
+        let module = &symbols[0].children.as_ref().unwrap()[0];
+        assert_eq!(module.detail.as_deref(), Some("module 0x2::Record"));
+    }
+
+    #[test]
+    fn test_document_symbols_of_module_and_script() {
+        let source_text = r"
+module M {
+    native struct S;
+    native public fun f(): u8;
+}
+
+script {
+    fun main(s: &signer) {}
+}
+";
+        let symbols = document_symbols((get_script_path(), source_text.to_string()), config!());
+        assert_eq!(
+            outline(&symbols, 0),
+            vec![
+                "M: Module",
+                "  S: Struct",
+                "  f: Function",
+                "script: Module",
+                "  main: Function",
+            ]
+        );
+        let main = &symbols[1].children.as_ref().unwrap()[0];
+        assert_eq!(main.detail.as_deref(), Some("fun main(s: &signer)"));
+        assert_eq!(main.kind, SymbolKind::Function);
+        assert_eq!(symbols[1].selection_range, main.selection_range);
+    }
+}
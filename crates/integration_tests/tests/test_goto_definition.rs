@@ -0,0 +1,264 @@
+use analysis::config::Config;
+use integration_tests::{global_state_snapshot, position_params};
+use move_language_server::handlers::handle_goto_definition;
+use move_language_server::req;
+
+use utils::{FileId, MoveFile};
+
+fn goto_definition(
+    file: MoveFile,
+    config: Config,
+    pos: (u64, u64),
+) -> Option<(FileId, (u64, u64))> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::GotoDefinitionParams {
+        text_document_position_params: position_params(pos, file.0),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+        partial_result_params: req::PartialResultParams::default(),
+    };
+    let location = match handle_goto_definition(snapshot, params).unwrap()? {
+        req::GotoDefinitionResponse::Scalar(location) => location,
+        _ => unreachable!(),
+    };
+    let fpath = utils::FileId::new(location.uri.to_file_path().unwrap());
+    let start = location.range.start;
+    Some((fpath, (start.line, start.character)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use analysis::analysis::AnalysisHost;
+    use analysis::change::AnalysisChange;
+    use analysis::db::FilePosition;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+    use utils::FileId;
+
+    fn script_definition(source_text: &str, pos: (u64, u64)) -> Option<(u64, u64)> {
+        let (fpath, start) = goto_definition(
+            (get_script_path(), source_text.to_string()),
+            config!({ "stdlib_folder": get_stdlib_path() }),
+            pos,
+        )?;
+        assert_eq!(fpath, get_script_path());
+        Some(start)
+    }
+
+    #[test]
+    fn test_goto_definition_of_local_variable_respects_shadowing() {
+        let source_text = r"
+script {
+    fun main() {
+        let a = 1;
+        let a = a + 1;
+        let _ = a;
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (4, 16)), Some((3, 12)));
+        assert_eq!(script_definition(source_text, (5, 16)), Some((4, 12)));
+    }
+
+    #[test]
+    fn test_goto_definition_of_function_parameter() {
+        let source_text = r"
+script {
+    fun main(account: &signer, amount: u64) {
+        let _ = copy amount;
+        let _ = account;
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (3, 22)), Some((2, 31)));
+        assert_eq!(script_definition(source_text, (4, 17)), Some((2, 13)));
+    }
+
+    #[test]
+    fn test_goto_definition_of_struct_fields() {
+        let source_text = r"
+module Record {
+    struct T { age: u8 }
+
+    fun create(): T {
+        T { age: 10 }
+    }
+
+    fun age(record: T): u8 {
+        let T { age: years } = record;
+        years
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (5, 13)), Some((2, 15)));
+        assert_eq!(script_definition(source_text, (9, 17)), Some((2, 15)));
+        assert_eq!(script_definition(source_text, (10, 9)), Some((9, 21)));
+    }
+
+    #[test]
+    fn test_goto_definition_of_type_parameter() {
+        let source_text = r"
+module Record {
+    struct T<Content: copyable> { content: Content }
+
+    fun wrap<Content: copyable>(content: Content): T<Content> {
+        T<Content> { content }
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (2, 45)), Some((2, 13)));
+        assert_eq!(script_definition(source_text, (4, 43)), Some((4, 13)));
+        assert_eq!(script_definition(source_text, (4, 55)), Some((4, 13)));
+        assert_eq!(script_definition(source_text, (5, 12)), Some((4, 13)));
+        assert_eq!(script_definition(source_text, (4, 51)), Some((2, 11)));
+    }
+
+    #[test]
+    fn test_goto_definition_of_stdlib_function_through_alias() {
+        let source_text = r"
+script {
+    use 0x1::Signer as S;
+    use 0x1::Signer::{address_of as addr};
+
+    fun main(s: &signer) {
+        let _ = S::address_of(s);
+        let _ = addr(s);
+    }
+}
+";
+        let config = config!({ "stdlib_folder": get_stdlib_path() });
+        let signer_fpath = FileId::new(get_stdlib_path().join("signer.move"));
+
+        let definition = goto_definition(
+            (get_script_path(), source_text.to_string()),
+            config.clone(),
+            (6, 21),
+        );
+        assert_eq!(definition, Some((signer_fpath, (12, 15))));
+
+        let definition = goto_definition(
+            (get_script_path(), source_text.to_string()),
+            config,
+            (7, 18),
+        );
+        assert_eq!(definition, Some((signer_fpath, (12, 15))));
+    }
+
+    #[test]
+    fn test_spec_functions_are_resolved_only_inside_specs() {
+        let source_text = r"
+module Bank {
+    spec module {
+        define balance_of(addr: address): u64 { 0 }
+    }
+
+    fun main() {
+        balance_of(0x1);
+    }
+
+    spec fun main {
+        ensures balance_of(0x1) == 0;
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (7, 8)), None);
+        assert_eq!(script_definition(source_text, (11, 16)), Some((3, 15)));
+    }
+
+    #[test]
+    fn test_no_definition_for_unknown_name() {
+        let source_text = r"
+script {
+    fun main() {
+        let _ = unknown;
+    }
+}
+";
+        assert_eq!(script_definition(source_text, (3, 18)), None);
+    }
+
+    #[test]
+    fn test_goto_definition_follows_changes_of_other_files() {
+        let module_fpath = FileId::new(get_modules_path().join("record.move"));
+        let source_text = r"
+script {
+    use 0x1::Record;
+
+    fun main() {
+        Record::create();
+    }
+}
+";
+        let mut analysis_host = AnalysisHost::default();
+        let mut change = AnalysisChange::new();
+        change.change_config(config!({ "modules_folders": [get_modules_path()] }));
+        change.add_file(
+            module_fpath,
+            "address 0x1 {\nmodule Record {\n    public fun create() {}\n}\n}".to_string(),
+        );
+        change.add_file(get_script_path(), source_text.to_string());
+        analysis_host.apply_change(change);
+
+        let position = || FilePosition {
+            fpath: get_script_path(),
+            pos: (5, 17),
+        };
+        let definition = analysis_host.analysis().goto_definition(position());
+        assert_eq!(definition.unwrap().range.start.line, 2);
+
+        let mut change = AnalysisChange::new();
+        change.update_file(
+            module_fpath,
+            "address 0x1 {\nmodule Record {\n    fun other() {}\n\n    public fun create() {}\n}\n}"
+                .to_string(),
+        );
+        analysis_host.apply_change(change);
+        let definition = analysis_host.analysis().goto_definition(position());
+        assert_eq!(definition.unwrap().range.start.line, 4);
+
+        let mut change = AnalysisChange::new();
+        change.remove_file(module_fpath);
+        analysis_host.apply_change(change);
+        assert!(analysis_host
+            .analysis()
+            .goto_definition(position())
+            .is_none());
+    }
+
+    #[test]
+    fn test_goto_definition_prefers_modules_of_tracked_files() {
+        let tracked_fpath = FileId::new(
+            get_stdlib_path()
+                .with_file_name("workspace")
+                .join("signer.move"),
+        );
+        let source_text = r"
+script {
+    use 0x1::Signer;
+
+    fun main(s: &signer) {
+        let _ = Signer::address_of(s);
+    }
+}
+";
+        let mut analysis_host = AnalysisHost::default();
+        let mut change = AnalysisChange::new();
+        change.change_config(config!({ "stdlib_folder": get_stdlib_path() }));
+        change.add_file(
+            tracked_fpath,
+            "address 0x1 {\nmodule Signer {\n    public fun address_of(s: &signer): address { 0x1 }\n}\n}"
+                .to_string(),
+        );
+        change.add_file(get_script_path(), source_text.to_string());
+        analysis_host.apply_change(change);
+
+        let definition = analysis_host
+            .analysis()
+            .goto_definition(FilePosition {
+                fpath: get_script_path(),
+                pos: (5, 26),
+            })
+            .unwrap();
+        assert_eq!(definition.fpath, tracked_fpath);
+        assert_eq!(definition.range.start.line, 2);
+    }
+}
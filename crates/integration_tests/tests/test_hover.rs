@@ -0,0 +1,197 @@
+use analysis::config::Config;
+use integration_tests::{global_state_snapshot, position_params};
+use lsp_types::HoverContents;
+use move_language_server::handlers::handle_hover;
+use move_language_server::req;
+
+use utils::MoveFile;
+
+fn hover_text(file: MoveFile, config: Config, pos: (u64, u64)) -> Option<String> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::HoverParams {
+        text_document_position_params: position_params(pos, file.0),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+    };
+    let hover = handle_hover(snapshot, params).unwrap()?;
+    match hover.contents {
+        HoverContents::Markup(content) => Some(content.value),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+
+    #[test]
+    fn test_hover_on_struct_from_stdlib_shows_doc_comments() {
+        let source_text = r"
+script {
+    use 0x1::Coins;
+
+    fun main() {
+        let _ = Coins::ETH {};
+    }
+}
+";
+        let config = config!({ "stdlib_folder": get_stdlib_path() });
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config,
+            (5, 25),
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "```move\n0x1::Coins\n```\n\n```move\nstruct ETH {}\n```"
+        );
+
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config!({ "stdlib_folder": get_stdlib_path() }),
+            (2, 14),
+        )
+        .unwrap();
+        assert!(
+            text.starts_with("```move\nmodule 0x1::Coins\n```\n\n---\n\nEnum-like module"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_hover_on_function_call_shows_signature() {
+        let source_text = r"
+script {
+    use 0x1::Signer;
+
+    fun main(s: &signer) {
+        let _ = Signer::address_of(s);
+    }
+}
+";
+        let config = config!({ "stdlib_folder": get_stdlib_path() });
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config,
+            (5, 26),
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "```move\n0x1::Signer\n```\n\n```move\npublic fun address_of(s: &signer): address\n```"
+        );
+    }
+
+    #[test]
+    fn test_hover_on_imported_member_and_struct_in_module_folder() {
+        let source_text = r"
+script {
+    use 0x2::Record::{get_record as get};
+
+    fun main(s: &signer) {
+        let _: 0x2::Record::T = get(0x1);
+    }
+}
+";
+        let config = config!({ "modules_folders": [get_modules_path()] });
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config,
+            (5, 33),
+        )
+        .unwrap();
+        assert!(
+            text.ends_with("```move\npublic fun get_record(addr: address): T acquires T\n```"),
+            "{}",
+            text
+        );
+
+        let config = config!({ "modules_folders": [get_modules_path()] });
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config,
+            (5, 29),
+        )
+        .unwrap();
+        assert!(
+            text.ends_with("```move\nresource struct T {\n    age: u8,\n}\n```"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_hover_on_local_shows_inferred_type() {
+        let source_text = r#"
+script {
+    fun main() {
+        let (num, bytes) = (1u8, x"01");
+        let _ = bytes;
+    }
+}
+"#;
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config!(),
+            (4, 18),
+        )
+        .unwrap();
+        assert_eq!(text, "```move\nlet bytes: vector<u8>\n```");
+    }
+
+    #[test]
+    fn test_no_hover_for_unresolved_names() {
+        let source_text = r"
+script {
+    fun main() {
+        let _ = Unknown::call();
+    }
+}
+";
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config!(),
+            (3, 27),
+        );
+        assert!(text.is_none());
+    }
+
+    #[test]
+    fn test_hover_on_local_with_half_typed_type_annotation() {
+        let source_text = r"
+script {
+    fun main(a: (u8, // amount
+                 u64)) {
+        let b = a;
+        let _ = b;
+    }
+}
+";
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config!(),
+            (5, 16),
+        )
+        .unwrap();
+        assert_eq!(text, "```move\nlet b: (u8, u64)\n```");
+
+        let source_text = r"
+script {
+    fun main() {
+        let a: vector< = 1;
+        let b = a;
+        let _ = b;
+    }
+}
+";
+        let text = hover_text(
+            (get_script_path(), source_text.to_string()),
+            config!(),
+            (5, 16),
+        )
+        .unwrap();
+        assert_eq!(text, "```move\nlet b: vector<_>\n```");
+    }
+}
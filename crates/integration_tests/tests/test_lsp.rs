@@ -1,21 +1,36 @@
 use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
-use lsp_types::request::{Initialize, Shutdown};
+use lsp_types::request::{Initialize, Shutdown, WorkspaceSymbol};
 use lsp_types::{
-    ClientCapabilities, DidChangeConfigurationParams, InitializeParams, InitializedParams,
+    CancelParams, ClientCapabilities, Diagnostic, DidChangeConfigurationParams,
+    DidChangeTextDocumentParams, DidOpenTextDocumentParams, InitializeParams, InitializedParams,
+    NumberOrString, Position, Range, TextDocumentContentChangeEvent, TextDocumentItem, Url,
+    VersionedTextDocumentIdentifier, WindowClientCapabilities, WorkspaceSymbolParams,
 };
 
+use analysis::analysis::AnalysisHost;
+use analysis::change::AnalysisChange;
 use analysis::config::Config;
+use analysis::db::FileDiagnostic;
 use crossbeam_channel::{unbounded, Sender};
 use dialects::DialectName;
+use lsp_server::ErrorCode;
 
 use integration_tests::config;
-use integration_tests::get_test_resources_dir;
-use lsp_types::notification::{DidChangeConfiguration, Initialized};
+use integration_tests::{get_modules_path, get_test_resources_dir};
+use lsp_types::notification::{
+    Cancel, DidChangeConfiguration, DidChangeTextDocument, DidOpenTextDocument, Initialized,
+};
 use move_language_server::fs::ws_root_vfs;
 use move_language_server::global_state::GlobalState;
-use move_language_server::main_loop::{main_loop, notification_new, request_new};
+use move_language_server::main_loop::{
+    loop_turn, main_loop, notification_new, request_new, Event, LoopState, Task,
+};
 use move_language_server::server::run_server;
 use ra_vfs::VfsTask;
+use std::thread;
+use std::time::Duration;
+use threadpool::ThreadPool;
+use utils::FileId;
 
 const SHUTDOWN_REQ_ID: u64 = 10;
 
@@ -120,7 +135,7 @@ fn test_server_initialization() {
     assert_eq!(init_finished_resp.id, RequestId::from(1));
     assert_eq!(
         init_finished_resp.result.unwrap()["capabilities"]["textDocumentSync"],
-        1
+        2
     );
     let shutdown_req = client_conn.receiver.try_recv().unwrap();
     assert_eq!(
@@ -147,9 +162,14 @@ fn test_server_config_change() {
     );
 
     let (mut global_state, _) = global_state(config!());
-    assert_eq!(global_state.config.dialect_name, DialectName::Libra);
+    assert_eq!(global_state.config.dialect_name, DialectName::default());
 
-    main_loop(&mut global_state, &server_conn).unwrap();
+    main_loop(
+        &mut global_state,
+        &server_conn,
+        &ClientCapabilities::default(),
+    )
+    .unwrap();
 
     assert_eq!(
         client_conn
@@ -160,5 +180,632 @@ fn test_server_config_change() {
             .method,
         "workspace/configuration"
     );
-    assert_eq!(global_state.config.dialect_name, DialectName::Starcoin);
+    assert_eq!(global_state.config.dialect_name.as_str(), "starcoin");
+}
+
+#[test]
+fn test_ranged_changes_are_applied_to_opened_file() {
+    let (client_conn, server_conn) = Connection::memory();
+
+    let fpath = get_modules_path().join("changed.move");
+    let uri = Url::from_file_path(&fpath).unwrap();
+    let didopen_notification = notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(
+            uri.clone(),
+            "move".to_string(),
+            1,
+            "script {\n    fun main() {}\n}\n".to_string(),
+        ),
+    });
+    let change =
+        |start: (u64, u64), end: (u64, u64), text: &str| TextDocumentContentChangeEvent {
+            range: Some(Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            )),
+            range_length: None,
+            text: text.to_string(),
+        };
+    let didchange_notification =
+        notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri, 2),
+            content_changes: vec![
+                change((1, 8), (1, 12), "start"),
+                change((1, 17), (1, 17), "\n        let a = 1;\n    "),
+            ],
+        });
+    send_messages(
+        &client_conn,
+        vec![didopen_notification, didchange_notification],
+    );
+
+    let (mut global_state, _) = global_state(config!());
+    main_loop(
+        &mut global_state,
+        &server_conn,
+        &ClientCapabilities::default(),
+    )
+    .unwrap();
+
+    let db = global_state.analysis_host.db();
+    let fpath = FileId::new(fpath);
+    let expected_text = "script {\n    fun start() {\n        let a = 1;\n    }\n}\n";
+    assert_eq!(db.file_text(fpath).unwrap(), expected_text);
+
+    // incrementally reparsed tree is the same as the one parsed from scratch
+    let mut analysis_host = AnalysisHost::default();
+    let mut change = AnalysisChange::new();
+    change.add_file(fpath, expected_text.to_string());
+    analysis_host.apply_change(change);
+    assert_eq!(
+        db.source_file(fpath).unwrap().tree.root_node().to_sexp(),
+        analysis_host
+            .db()
+            .source_file(fpath)
+            .unwrap()
+            .tree
+            .root_node()
+            .to_sexp()
+    );
+}
+
+#[test]
+fn test_invalid_changes_are_dropped_without_stopping_server() {
+    let (client_conn, server_conn) = Connection::memory();
+
+    let fpath = get_modules_path().join("changed.move");
+    let uri = Url::from_file_path(&fpath).unwrap();
+    let didopen_notification = notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(
+            uri.clone(),
+            "move".to_string(),
+            1,
+            "script {\n    fun main() {}\n}\n".to_string(),
+        ),
+    });
+    let change =
+        |start: (u64, u64), end: (u64, u64), text: &str| TextDocumentContentChangeEvent {
+            range: Some(Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            )),
+            range_length: None,
+            text: text.to_string(),
+        };
+    let valid_change_notification =
+        notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![change((1, 8), (1, 12), "start")],
+        });
+    let invalid_change_notification =
+        notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 3),
+            content_changes: vec![
+                change((1, 8), (1, 13), "end"),
+                change((1, 12), (1, 4), "fun"),
+            ],
+        });
+    let empty_change_notification =
+        notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri, 4),
+            content_changes: vec![],
+        });
+    send_messages(
+        &client_conn,
+        vec![
+            didopen_notification,
+            valid_change_notification,
+            invalid_change_notification,
+            empty_change_notification,
+        ],
+    );
+
+    let (mut global_state, _) = global_state(config!());
+    main_loop(
+        &mut global_state,
+        &server_conn,
+        &ClientCapabilities::default(),
+    )
+    .unwrap();
+
+    // valid change of the invalid notification is dropped too
+    let db = global_state.analysis_host.db();
+    assert_eq!(
+        db.file_text(FileId::new(fpath)).unwrap(),
+        "script {\n    fun start() {}\n}\n"
+    );
+}
+
+#[test]
+fn test_cancelled_request_is_answered_once() {
+    let (client_conn, server_conn) = Connection::memory();
+
+    let symbols_request = request_new::<WorkspaceSymbol>(
+        RequestId::from(2),
+        WorkspaceSymbolParams {
+            partial_result_params: Default::default(),
+            work_done_progress_params: Default::default(),
+            query: "main".to_string(),
+        },
+    );
+    let cancel = |id: u64| {
+        notification::<Cancel>(CancelParams {
+            id: NumberOrString::Number(id),
+        })
+    };
+    send_messages(
+        &client_conn,
+        vec![Message::Request(symbols_request), cancel(2), cancel(3)],
+    );
+
+    let (mut global_state, _) = global_state(config!());
+    main_loop(
+        &mut global_state,
+        &server_conn,
+        &ClientCapabilities::default(),
+    )
+    .unwrap();
+
+    let responses: Vec<Response> = client_conn
+        .receiver
+        .try_iter()
+        .filter_map(|message| match message {
+            Message::Response(response) if response.id != RequestId::from(SHUTDOWN_REQ_ID) => {
+                Some(response)
+            }
+            _ => None,
+        })
+        .collect();
+    // request is either cancelled, or answered before the cancellation arrives
+    assert_eq!(responses.len(), 1, "{:#?}", responses);
+    assert_eq!(responses[0].id, RequestId::from(2));
+    if let Some(error) = &responses[0].error {
+        assert_eq!(error.code, ErrorCode::RequestCanceled as i32);
+    }
+}
+
+#[test]
+fn test_cancelled_request_is_not_computed_if_not_started() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!());
+    let mut loop_state = LoopState::new(&ClientCapabilities::default());
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, task_receiver) = unbounded::<Task>();
+
+    // the only worker is busy until the request is cancelled
+    let (unblock_sender, unblock_receiver) = unbounded::<()>();
+    pool.execute(move || unblock_receiver.recv().unwrap());
+
+    let symbols_request = request_new::<WorkspaceSymbol>(
+        RequestId::from(2),
+        WorkspaceSymbolParams {
+            partial_result_params: Default::default(),
+            work_done_progress_params: Default::default(),
+            query: "main".to_string(),
+        },
+    );
+    let cancel = notification::<Cancel>(CancelParams {
+        id: NumberOrString::Number(2),
+    });
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![
+            Event::Msg(Message::Request(symbols_request)),
+            Event::Msg(cancel),
+        ],
+    );
+    unblock_sender.send(()).unwrap();
+    pool.join();
+
+    let responses: Vec<Task> = task_receiver
+        .try_iter()
+        .filter(|task| matches!(task, Task::Respond(_)))
+        .collect();
+    assert!(responses.is_empty(), "{:#?}", responses);
+    let cancelled = client_conn
+        .receiver
+        .try_iter()
+        .filter(|message| {
+            matches!(message, Message::Response(response)
+            if response.error.as_ref().map(|error| error.code)
+                == Some(ErrorCode::RequestCanceled as i32))
+        })
+        .count();
+    assert_eq!(cancelled, 1);
+}
+
+fn process_events(
+    pool: &mut ThreadPool,
+    task_sender: &Sender<Task>,
+    server_conn: &Connection,
+    global_state: &mut GlobalState,
+    loop_state: &mut LoopState,
+    events: Vec<Event>,
+) {
+    for event in events {
+        loop_turn(
+            pool,
+            task_sender,
+            server_conn,
+            global_state,
+            loop_state,
+            event,
+        )
+        .unwrap();
+    }
+}
+
+fn progress_create_request_ids(messages: &[Message]) -> Vec<RequestId> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Request(req) if req.method == "window/workDoneProgress/create" => {
+                Some(req.id.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn progress_notifications(messages: &[Message]) -> Vec<(String, String)> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Notification(not) if not.method == "$/progress" => Some((
+                not.params["value"]["kind"].as_str().unwrap().to_string(),
+                not.params["value"]["message"].as_str().unwrap().to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn published_diagnostics_count(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .filter(|message| {
+            matches!(message, Message::Notification(not)
+                if not.method == "textDocument/publishDiagnostics")
+        })
+        .count()
+}
+
+fn progress_client_caps() -> ClientCapabilities {
+    ClientCapabilities {
+        window: Some(WindowClientCapabilities {
+            work_done_progress: Some(true),
+        }),
+        ..ClientCapabilities::default()
+    }
+}
+
+#[test]
+fn test_edits_are_checked_after_debounce_window() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!({"diagnostics_debounce_ms": 500}));
+    let mut loop_state = LoopState::new(&progress_client_caps());
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, task_receiver) = unbounded::<Task>();
+
+    let uri = Url::from_file_path(get_modules_path().join("debounced.move")).unwrap();
+    let didopen_notification = notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(
+            uri.clone(),
+            "move".to_string(),
+            1,
+            "script {\n    fun main() {}\n}\n".to_string(),
+        ),
+    });
+    let didchange_notification =
+        notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri, 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(1, 8), Position::new(1, 12))),
+                range_length: None,
+                text: "start".to_string(),
+            }],
+        });
+    // opened file is checked right away, the edit waits for the debounce window
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![
+            Event::Msg(didopen_notification),
+            Event::Msg(didchange_notification),
+            Event::DiagnosticsDue,
+        ],
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    let request_ids = progress_create_request_ids(&messages);
+    assert_eq!(request_ids.len(), 1, "{:#?}", messages);
+    // progress begins only after the client creates it
+    assert!(progress_notifications(&messages).is_empty());
+
+    pool.join();
+    let mut events = vec![Event::Msg(Message::Response(Response::new_ok(
+        request_ids[0].clone(),
+        serde_json::Value::Null,
+    )))];
+    events.extend(task_receiver.try_iter().map(Event::Task));
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        events,
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    assert!(progress_create_request_ids(&messages).is_empty());
+    assert!(published_diagnostics_count(&messages) > 0);
+    assert_eq!(
+        progress_notifications(&messages),
+        vec![
+            ("begin".to_string(), "0/1 files".to_string()),
+            ("end".to_string(), "1/1 files".to_string())
+        ]
+    );
+
+    // edit is checked once the window is over
+    thread::sleep(Duration::from_millis(600));
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![Event::DiagnosticsDue],
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    let request_ids = progress_create_request_ids(&messages);
+    assert_eq!(request_ids.len(), 1, "{:#?}", messages);
+
+    pool.join();
+    let mut events = vec![Event::Msg(Message::Response(Response::new_ok(
+        request_ids[0].clone(),
+        serde_json::Value::Null,
+    )))];
+    events.extend(task_receiver.try_iter().map(Event::Task));
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        events,
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    assert!(published_diagnostics_count(&messages) > 0);
+    assert_eq!(
+        progress_notifications(&messages),
+        vec![
+            ("begin".to_string(), "0/1 files".to_string()),
+            ("end".to_string(), "1/1 files".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_progress_is_not_reported_if_client_fails_to_create_it() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!());
+    let mut loop_state = LoopState::new(&progress_client_caps());
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, task_receiver) = unbounded::<Task>();
+
+    let uri = Url::from_file_path(get_modules_path().join("progress.move")).unwrap();
+    let didopen_notification = notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(
+            uri,
+            "move".to_string(),
+            1,
+            "script {\n    fun main() {}\n}\n".to_string(),
+        ),
+    });
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![Event::Msg(didopen_notification)],
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    let request_ids = progress_create_request_ids(&messages);
+    assert_eq!(request_ids.len(), 1, "{:#?}", messages);
+
+    pool.join();
+    let mut events = vec![Event::Msg(Message::Response(Response::new_err(
+        request_ids[0].clone(),
+        ErrorCode::InternalError as i32,
+        "progress is not supported".to_string(),
+    )))];
+    events.extend(task_receiver.try_iter().map(Event::Task));
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        events,
+    );
+    let messages: Vec<Message> = client_conn.receiver.try_iter().collect();
+    assert!(published_diagnostics_count(&messages) > 0);
+    assert!(progress_notifications(&messages).is_empty());
+}
+
+/// Messages of the diagnostics published for the file, sorted per publish.
+fn published_messages(client_conn: &Connection, fpath: FileId) -> Vec<Vec<String>> {
+    let uri = Url::from_file_path(fpath).unwrap();
+    client_conn
+        .receiver
+        .try_iter()
+        .filter_map(|message| match message {
+            Message::Notification(not)
+                if not.method == "textDocument/publishDiagnostics"
+                    && not.params["uri"] == uri.as_str() =>
+            {
+                let mut messages: Vec<String> = not.params["diagnostics"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|diagnostic| diagnostic["message"].as_str().unwrap().to_string())
+                    .collect();
+                messages.sort();
+                Some(messages)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_diagnostics_of_shared_dependency_are_merged_across_checks() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!());
+    let mut loop_state = LoopState::new(&ClientCapabilities::default());
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, _) = unbounded::<Task>();
+
+    let fpath = |name: &str| FileId::new(get_modules_path().join(name));
+    let (script_a, script_b, dependency) = (fpath("a.move"), fpath("b.move"), fpath("dep.move"));
+    let error = |message: &str| {
+        let diagnostic = Diagnostic::new_simple(Range::default(), message.to_string());
+        FileDiagnostic::new(dependency, diagnostic)
+    };
+    // checks of both scripts report errors located in the module they use
+    let checks = vec![
+        (
+            script_a,
+            vec![FileDiagnostic::new_empty(script_a), error("Error via a")],
+        ),
+        (
+            script_b,
+            vec![FileDiagnostic::new_empty(script_b), error("Error via b")],
+        ),
+        (script_a, vec![FileDiagnostic::new_empty(script_a)]),
+    ];
+    for (checked, file_diagnostics) in checks {
+        loop_turn(
+            &mut pool,
+            &task_sender,
+            &server_conn,
+            &mut global_state,
+            &mut loop_state,
+            Event::Task(Task::Diagnostic(0, checked, file_diagnostics)),
+        )
+        .unwrap();
+    }
+
+    let published = published_messages(&client_conn, dependency);
+    assert_eq!(
+        published,
+        vec![
+            vec!["Error via a".to_string()],
+            vec!["Error via a".to_string(), "Error via b".to_string()],
+            vec!["Error via b".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn test_fixed_dependency_errors_are_cleared_for_unrelated_checks() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!());
+    let mut loop_state = LoopState::new(&ClientCapabilities::default());
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, _) = unbounded::<Task>();
+
+    let fpath = |name: &str| FileId::new(get_modules_path().join(name));
+    let (script, module) = (fpath("unrelated.move"), fpath("broken.move"));
+    let error = FileDiagnostic::new(
+        module,
+        Diagnostic::new_simple(Range::default(), "Error in module".to_string()),
+    );
+    // script doesn't use the module, but its check compiles the module too
+    let checks = vec![
+        (
+            0,
+            script,
+            vec![FileDiagnostic::new_empty(script), error.clone()],
+        ),
+        (0, module, vec![error]),
+        // module is fixed, only the module itself is checked again
+        (1, module, vec![FileDiagnostic::new_empty(module)]),
+    ];
+    for (revision, checked, file_diagnostics) in checks {
+        loop_turn(
+            &mut pool,
+            &task_sender,
+            &server_conn,
+            &mut global_state,
+            &mut loop_state,
+            Event::Task(Task::Diagnostic(revision, checked, file_diagnostics)),
+        )
+        .unwrap();
+    }
+
+    let published = published_messages(&client_conn, module);
+    assert_eq!(published.last(), Some(&vec![]), "{:#?}", published);
+}
+
+#[test]
+fn test_checks_after_config_reload_are_published() {
+    let (client_conn, server_conn) = Connection::memory();
+    let (mut global_state, _) = global_state(config!());
+    let config_request_id = RequestId::from(1);
+    let mut loop_state = LoopState::with_config_request_id(&config_request_id);
+    let mut pool = ThreadPool::new(1);
+    let (task_sender, _task_receiver) = unbounded::<Task>();
+
+    let fpath = get_modules_path().join("reloaded.move");
+    let didopen_notification = notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(
+            Url::from_file_path(&fpath).unwrap(),
+            "move".to_string(),
+            1,
+            "script {\n    fun main() {}\n}\n".to_string(),
+        ),
+    });
+    let config_response = Response::new_ok(config_request_id, vec![serde_json::json!({})]);
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![
+            Event::Msg(didopen_notification),
+            Event::Msg(Message::Response(config_response)),
+        ],
+    );
+    pool.join();
+    client_conn.receiver.try_iter().for_each(drop);
+
+    // database of the reloaded config starts over, its results are still the latest ones
+    let fpath = FileId::new(fpath);
+    let error = FileDiagnostic::new(
+        fpath,
+        Diagnostic::new_simple(Range::default(), "Error after reload".to_string()),
+    );
+    let revision = global_state.analysis_host.db().revision();
+    process_events(
+        &mut pool,
+        &task_sender,
+        &server_conn,
+        &mut global_state,
+        &mut loop_state,
+        vec![Event::Task(Task::Diagnostic(revision, fpath, vec![error]))],
+    );
+    assert_eq!(
+        published_messages(&client_conn, fpath),
+        vec![vec!["Error after reload".to_string()]]
+    );
 }
@@ -0,0 +1,154 @@
+use analysis::config::Config;
+use integration_tests::{global_state_snapshot, position_params};
+use move_language_server::handlers::{handle_document_highlight, handle_references};
+use move_language_server::req;
+
+use utils::{FileId, MoveFile};
+
+fn references(
+    file: MoveFile,
+    config: Config,
+    pos: (u64, u64),
+    include_declaration: bool,
+) -> Vec<(FileId, (u64, u64))> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::ReferenceParams {
+        text_document_position: position_params(pos, file.0),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+        partial_result_params: req::PartialResultParams::default(),
+        context: lsp_types::ReferenceContext {
+            include_declaration,
+        },
+    };
+    handle_references(snapshot, params)
+        .unwrap()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|location| {
+            let fpath = FileId::new(location.uri.to_file_path().unwrap());
+            let start = location.range.start;
+            (fpath, (start.line, start.character))
+        })
+        .collect()
+}
+
+fn highlights(file: MoveFile, config: Config, pos: (u64, u64)) -> Vec<(u64, u64)> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::DocumentHighlightParams {
+        text_document_position_params: position_params(pos, file.0),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+        partial_result_params: req::PartialResultParams::default(),
+    };
+    handle_document_highlight(snapshot, params)
+        .unwrap()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|highlight| (highlight.range.start.line, highlight.range.start.character))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+
+    const SCRIPT: &str = r"
+script {
+    use 0x2::Record;
+    use 0x2::Record::{get_record as get};
+
+    fun main(s: &signer) {
+        let record = Record::get_record(0x2);
+        Record::save(s, record);
+        let _ = get(0x2);
+    }
+}
+";
+
+    fn config() -> Config {
+        config!({
+            "stdlib_folder": get_stdlib_path(),
+            "modules_folders": [get_modules_path()],
+        })
+    }
+
+    #[test]
+    fn test_references_of_module_function_across_files() {
+        let record_fpath = FileId::new(get_modules_path().join("record.move"));
+        let script_fpath = get_script_path();
+
+        let refs = references((script_fpath, SCRIPT.to_string()), config(), (6, 30), false);
+        assert_eq!(
+            refs,
+            vec![
+                (record_fpath, (21, 31)),
+                (record_fpath, (26, 21)),
+                (script_fpath, (3, 22)),
+                (script_fpath, (3, 36)),
+                (script_fpath, (6, 29)),
+                (script_fpath, (8, 16)),
+            ]
+        );
+
+        let refs = references((script_fpath, SCRIPT.to_string()), config(), (8, 17), true);
+        assert_eq!(refs.len(), 7);
+        assert!(refs.contains(&(record_fpath, (8, 19))));
+    }
+
+    #[test]
+    fn test_references_of_local_variable_stay_in_file() {
+        let script_fpath = get_script_path();
+        let refs = references((script_fpath, SCRIPT.to_string()), config(), (7, 21), true);
+        assert_eq!(refs, vec![(script_fpath, (5, 13)), (script_fpath, (7, 21))]);
+    }
+
+    #[test]
+    fn test_document_highlight_of_local_variable() {
+        let ranges = highlights((get_script_path(), SCRIPT.to_string()), config(), (7, 25));
+        assert_eq!(ranges, vec![(6, 12), (7, 24)]);
+    }
+
+    #[test]
+    fn test_reference_columns_after_multibyte_text_are_utf16_code_units() {
+        // comments run to the end of the line, so the multibyte text before `record`
+        // on the same line is an unfinished statement
+        let source_text = r"
+script {
+    fun main() {
+        // ключ
+        let record = 1;
+        ключ; let _ = record;
+    }
+}
+";
+        let script_fpath = get_script_path();
+        let refs = references(
+            (script_fpath, source_text.to_string()),
+            config(),
+            (5, 23),
+            true,
+        );
+        assert_eq!(refs, vec![(script_fpath, (4, 12)), (script_fpath, (5, 22))]);
+
+        let ranges = highlights((script_fpath, source_text.to_string()), config(), (5, 28));
+        assert_eq!(ranges, vec![(4, 12), (5, 22)]);
+    }
+
+    #[test]
+    fn test_no_references_for_unresolved_name() {
+        let source_text = r"
+script {
+    fun main() {
+        unknown();
+    }
+}
+";
+        let refs = references(
+            (get_script_path(), source_text.to_string()),
+            config(),
+            (3, 9),
+            true,
+        );
+        assert!(refs.is_empty());
+    }
+}
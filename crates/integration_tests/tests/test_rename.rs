@@ -0,0 +1,253 @@
+use analysis::config::Config;
+use integration_tests::{global_state_snapshot, position_params};
+use move_language_server::handlers::{handle_prepare_rename, handle_rename};
+use move_language_server::req;
+
+use utils::{FileId, MoveFile};
+
+type Edit = (FileId, (u64, u64), String);
+
+fn rename(
+    file: MoveFile,
+    config: Config,
+    pos: (u64, u64),
+    new_name: &str,
+) -> Result<Vec<Edit>, String> {
+    let snapshot = global_state_snapshot(file.clone(), config, vec![]);
+    let params = req::RenameParams {
+        text_document_position: position_params(pos, file.0),
+        new_name: new_name.to_string(),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+    };
+    let workspace_edit = handle_rename(snapshot, params)
+        .map_err(|err| err.to_string())?
+        .unwrap();
+    let mut edits = vec![];
+    for (uri, text_edits) in workspace_edit.changes.unwrap() {
+        let fpath = FileId::new(uri.to_file_path().unwrap());
+        for edit in text_edits {
+            let start = edit.range.start;
+            edits.push((fpath, (start.line, start.character), edit.new_text));
+        }
+    }
+    edits.sort();
+    Ok(edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+
+    const RECORD_MODULE: &str = r"
+module Record {
+    struct T { age: u8 }
+
+    fun create(age: u8): T {
+        T { age }
+    }
+
+    fun age_of(record: T): u8 {
+        let T { age } = record;
+        age
+    }
+}
+";
+
+    fn config() -> Config {
+        config!({
+            "stdlib_folder": get_stdlib_path(),
+            "modules_folders": [get_modules_path()],
+        })
+    }
+
+    fn rename_in_module(pos: (u64, u64), new_name: &str) -> Result<Vec<Edit>, String> {
+        rename(
+            (get_script_path(), RECORD_MODULE.to_string()),
+            config!({ "stdlib_folder": get_stdlib_path() }),
+            pos,
+            new_name,
+        )
+    }
+
+    #[test]
+    fn test_rename_module_function_updates_calls_and_imports() {
+        let source_text = r"
+script {
+    use 0x2::Record;
+    use 0x2::Record::{get_record, get_record as get};
+
+    fun main() {
+        let _ = Record::get_record(0x2);
+        let _ = get_record(0x2);
+        let _ = get(0x2);
+    }
+}
+";
+        let record_fpath = FileId::new(get_modules_path().join("record.move"));
+        let script_fpath = get_script_path();
+        let edits = rename(
+            (script_fpath, source_text.to_string()),
+            config(),
+            (6, 30),
+            "fetch",
+        )
+        .unwrap();
+        let fetch = || "fetch".to_string();
+        assert_eq!(
+            edits,
+            vec![
+                (record_fpath, (8, 19), fetch()),
+                (record_fpath, (21, 31), fetch()),
+                (record_fpath, (26, 21), fetch()),
+                (script_fpath, (3, 22), fetch()),
+                (script_fpath, (3, 34), fetch()),
+                (script_fpath, (6, 24), fetch()),
+                (script_fpath, (7, 16), fetch()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_field_expands_shorthands() {
+        let fpath = get_script_path();
+        let edits = rename_in_module((2, 16), "years").unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                (fpath, (2, 15), "years".to_string()),
+                (fpath, (5, 12), "years: age".to_string()),
+                (fpath, (9, 16), "years: age".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_locals_and_parameters_used_in_shorthands() {
+        let fpath = get_script_path();
+        let edits = rename_in_module((4, 16), "value").unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                (fpath, (4, 15), "value".to_string()),
+                (fpath, (5, 12), "age: value".to_string()),
+            ]
+        );
+
+        let edits = rename_in_module((10, 9), "n").unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                (fpath, (9, 16), "age: n".to_string()),
+                (fpath, (10, 8), "n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_refuses_name_collisions() {
+        let err = rename_in_module((4, 10), "age_of").unwrap_err();
+        assert!(
+            err.contains("clashes with the existing fun age_of"),
+            "{}",
+            err
+        );
+
+        let err = rename_in_module((10, 9), "record").unwrap_err();
+        assert!(
+            err.contains("clashes with the existing record: T"),
+            "{}",
+            err
+        );
+
+        let err = rename_in_module((2, 12), "fun").unwrap_err();
+        assert!(err.contains("'fun' is not a valid identifier"), "{}", err);
+    }
+
+    #[test]
+    fn test_builtin_type_names_are_not_valid_identifiers() {
+        for name in &["u8", "u64", "u128", "bool", "address", "signer", "vector"] {
+            let err = rename_in_module((4, 10), name).unwrap_err();
+            assert!(
+                err.contains(&format!("'{}' is not a valid identifier", name)),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_rename_edit_columns_after_multibyte_text_are_utf16_code_units() {
+        let source_text = r"
+script {
+    fun main() {
+        let record = 1;
+        ключ; let _ = record;
+    }
+}
+";
+        let fpath = get_script_path();
+        let edits = rename((fpath, source_text.to_string()), config(), (4, 23), "rec").unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                (fpath, (3, 12), "rec".to_string()),
+                (fpath, (4, 22), "rec".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spec_functions_do_not_clash_with_code_functions() {
+        let source_text = r"
+module Bank {
+    spec module {
+        define balance_of(addr: address): u64 { 0 }
+    }
+
+    fun balance(addr: address): u64 { 0 }
+}
+";
+        let edits = rename(
+            (get_script_path(), source_text.to_string()),
+            config(),
+            (6, 8),
+            "balance_of",
+        )
+        .unwrap();
+        assert_eq!(
+            edits,
+            vec![(get_script_path(), (6, 8), "balance_of".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_stdlib_items_cannot_be_renamed() {
+        let source_text = r"
+script {
+    use 0x1::Signer;
+
+    fun main(s: &signer) {
+        let _ = Signer::address_of(s);
+    }
+}
+";
+        let snapshot = global_state_snapshot(
+            (get_script_path(), source_text.to_string()),
+            config(),
+            vec![],
+        );
+        let params = position_params((5, 26), get_script_path());
+        let range = handle_prepare_rename(snapshot, params).unwrap();
+        assert!(range.is_none());
+
+        let err = rename(
+            (get_script_path(), source_text.to_string()),
+            config(),
+            (5, 26),
+            "signer_address",
+        )
+        .unwrap_err();
+        assert!(err.contains("declared in the stdlib"), "{}", err);
+    }
+}
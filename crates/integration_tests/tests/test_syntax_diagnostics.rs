@@ -0,0 +1,106 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use analysis::config::Config;
+use integration_tests::{get_script_path, global_state_snapshot};
+
+use crossbeam_channel::unbounded;
+use move_language_server::main_loop::{compute_file_diagnostics, Task};
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Range {
+    Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+/// Diagnostics of the first published task, which comes before the compiler run.
+fn syntax_diagnostics(text: &str) -> Vec<Diagnostic> {
+    published_diagnostics(text).remove(0)
+}
+
+/// Diagnostics of the file from every published task, in order.
+fn published_diagnostics(text: &str) -> Vec<Vec<Diagnostic>> {
+    let fpath = get_script_path();
+    let state_snapshot =
+        global_state_snapshot((fpath, text.to_string()), Config::default(), vec![]);
+    let (task_sender, task_receiver) = unbounded::<Task>();
+
+    compute_file_diagnostics(state_snapshot.analysis, task_sender, vec![fpath]);
+
+    task_receiver
+        .try_iter()
+        .map(|task| match task {
+            Task::Diagnostic(_, _, mut ds) => {
+                assert_eq!(ds[0].fpath, fpath);
+                ds.remove(0).diagnostics
+            }
+            _ => panic!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_syntax_errors_for_valid_file() {
+        let source_text = r"
+script {
+    fun main(s: &signer) {
+        let a = 1;
+    }
+}
+";
+        assert!(syntax_diagnostics(source_text).is_empty());
+    }
+
+    #[test]
+    fn test_unexpected_token_is_reported_before_compiler_run() {
+        let diagnostics = syntax_diagnostics("module M { struc S { f: u64 } }");
+        assert!(!diagnostics.is_empty());
+
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.message, "Unexpected 'struc'");
+        assert_eq!(diagnostic.range, range((0, 11), (0, 16)));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Error));
+    }
+
+    #[test]
+    fn test_several_syntax_errors_are_reported_at_once() {
+        let source_text = r"
+script {
+    fun main() {
+        let a = 1
+        let b = 2;
+        foo(
+    }
+}
+";
+        let diagnostics = syntax_diagnostics(source_text);
+        let ranges: Vec<_> = diagnostics.iter().map(|d| d.range).collect();
+        assert_eq!(
+            ranges,
+            vec![range((3, 8), (3, 11)), range((5, 11), (5, 12))]
+        );
+        assert_eq!(diagnostics[0].message, "Unexpected 'let'");
+        assert_eq!(diagnostics[1].message, "Unexpected '('");
+    }
+
+    #[test]
+    fn test_syntax_errors_are_kept_after_compiler_run() {
+        let source_text = r"
+script {
+    fun main() {
+        let a = 1
+        let b = 2;
+        foo(
+    }
+}
+";
+        let published = published_diagnostics(source_text);
+        assert_eq!(published.len(), 2);
+        // compiler reports only the first error, the parser finds the later ones too
+        let diagnostics = published.last().unwrap();
+        assert_eq!(diagnostics.len(), 2, "{:#?}", diagnostics);
+        assert_eq!(diagnostics[1].range, range((5, 11), (5, 12)));
+        assert_eq!(diagnostics[1].message, "Unexpected '('");
+    }
+}
@@ -0,0 +1,92 @@
+use analysis::analysis::AnalysisHost;
+use analysis::change::AnalysisChange;
+use analysis::db::FilePosition;
+use integration_tests::get_script_path;
+
+fn expr_type(source_text: &str, pos: (usize, usize)) -> Option<String> {
+    let mut analysis_host = AnalysisHost::default();
+    let mut change = AnalysisChange::new();
+    change.add_file(get_script_path(), source_text.to_string());
+    analysis_host.apply_change(change);
+
+    let position = FilePosition {
+        fpath: get_script_path(),
+        pos,
+    };
+    analysis_host
+        .analysis()
+        .expr_type(position)
+        .map(|ty| ty.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = r#"
+module Wallet {
+    struct Box<Content> { content: Content }
+    resource struct Balance { value: u128 }
+
+    fun wrap<Content>(content: Content): Box<Content> {
+        Box { content }
+    }
+
+    fun main(addr: address) acquires Balance {
+        let balance = borrow_global_mut<Balance>(addr);
+        let boxed = wrap(&balance.value);
+        let Box { content } = copy boxed;
+        let (num, bytes) = (*content + 1, x"01");
+        let _ = (num > 10) && !exists<Balance>(addr);
+        let _ = (num as u8);
+        let _ = Box<bool> { content: true }.content;
+    }
+}
+"#;
+
+    fn wallet_type(pos: (usize, usize)) -> Option<String> {
+        expr_type(WALLET, pos)
+    }
+
+    #[test]
+    fn test_type_of_literals_and_operators() {
+        assert_eq!(wallet_type((13, 42)), Some("vector<u8>".to_string()));
+        assert_eq!(wallet_type((13, 37)), Some("u128".to_string()));
+        assert_eq!(wallet_type((14, 27)), Some("bool".to_string()));
+        assert_eq!(wallet_type((15, 21)), Some("u8".to_string()));
+    }
+
+    #[test]
+    fn test_type_of_references_and_fields() {
+        assert_eq!(wallet_type((11, 26)), Some("&mut Balance".to_string()));
+        assert_eq!(wallet_type((11, 34)), Some("u128".to_string()));
+        assert_eq!(wallet_type((11, 25)), Some("&u128".to_string()));
+    }
+
+    #[test]
+    fn test_type_of_generic_instantiations() {
+        assert_eq!(wallet_type((6, 8)), Some("Box<Content>".to_string()));
+        assert_eq!(wallet_type((11, 20)), Some("Box<&u128>".to_string()));
+        assert_eq!(wallet_type((16, 44)), Some("bool".to_string()));
+    }
+
+    #[test]
+    fn test_type_of_locals_bound_by_patterns() {
+        assert_eq!(wallet_type((12, 35)), Some("Box<&u128>".to_string()));
+        assert_eq!(wallet_type((13, 29)), Some("&u128".to_string()));
+        assert_eq!(wallet_type((14, 17)), Some("u128".to_string()));
+    }
+
+    #[test]
+    fn test_type_of_unresolved_name_is_unknown() {
+        let source_text = r"
+script {
+    fun main() {
+        let _ = unknown.field;
+    }
+}
+";
+        assert_eq!(expr_type(source_text, (3, 18)), Some("_".to_string()));
+        assert_eq!(expr_type(source_text, (1, 2)), None);
+    }
+}
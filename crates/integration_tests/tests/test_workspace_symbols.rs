@@ -0,0 +1,123 @@
+use analysis::config::Config;
+use integration_tests::global_state_snapshot;
+use lsp_types::{SymbolInformation, SymbolKind};
+use move_language_server::handlers::handle_workspace_symbol;
+use move_language_server::req;
+
+use utils::MoveFile;
+
+fn workspace_symbols(file: MoveFile, config: Config, query: &str) -> Vec<SymbolInformation> {
+    let snapshot = global_state_snapshot(file, config, vec![]);
+    let params = req::WorkspaceSymbolParams {
+        partial_result_params: req::PartialResultParams::default(),
+        work_done_progress_params: req::WorkDoneProgressParams::default(),
+        query: query.to_string(),
+    };
+    handle_workspace_symbol(snapshot, params).unwrap().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use integration_tests::{config, get_modules_path, get_script_path, get_stdlib_path};
+    use utils::FileId;
+
+    const MODULE: &str = r"
+address 0x3 {
+    module Wallet {
+        resource struct Balance { value: u64 }
+
+        public fun balance_of(addr: address): u64 {
+            0
+        }
+
+        spec module {
+            define total_balance(addr: address): u64 { 0 }
+        }
+    }
+}
+";
+
+    fn config() -> Config {
+        config!({
+            "stdlib_folder": get_stdlib_path(),
+            "modules_folders": [get_modules_path()],
+        })
+    }
+
+    fn names(symbols: &[SymbolInformation]) -> Vec<&str> {
+        symbols.iter().map(|symbol| symbol.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_workspace_symbols_from_stdlib_are_fully_qualified() {
+        let symbols = workspace_symbols(
+            (get_script_path(), MODULE.to_string()),
+            config(),
+            "address_of",
+        );
+        assert_eq!(names(&symbols), vec!["0x1::Signer::address_of"]);
+
+        let symbol = &symbols[0];
+        assert_eq!(symbol.kind, SymbolKind::Function);
+        assert_eq!(symbol.container_name.as_deref(), Some("0x1::Signer"));
+        assert_eq!(
+            FileId::new(symbol.location.uri.to_file_path().unwrap()),
+            FileId::new(get_stdlib_path().join("signer.move"))
+        );
+        assert_eq!(symbol.location.range.start.line, 12);
+    }
+
+    #[test]
+    fn test_workspace_symbols_fuzzy_match_items_of_tracked_files_and_module_folders() {
+        let symbols = workspace_symbols((get_script_path(), MODULE.to_string()), config(), "bal");
+        assert_eq!(
+            names(&symbols),
+            vec![
+                "0x3::Wallet::Balance",
+                "0x3::Wallet::balance_of",
+                "0x3::Wallet::total_balance",
+            ]
+        );
+        assert_eq!(symbols[0].kind, SymbolKind::Struct);
+
+        let symbols =
+            workspace_symbols((get_script_path(), MODULE.to_string()), config(), "record");
+        assert_eq!(
+            names(&symbols),
+            vec![
+                "0x2::Record",
+                "0x2::Record::destroy_record",
+                "0x2::Record::get_record"
+            ]
+        );
+        assert_eq!(symbols[0].kind, SymbolKind::Module);
+    }
+
+    #[test]
+    fn test_workspace_symbols_match_names_qualified_with_module() {
+        let symbols = workspace_symbols(
+            (get_script_path(), MODULE.to_string()),
+            config(),
+            "Wallet::balance",
+        );
+        assert_eq!(
+            names(&symbols),
+            vec![
+                "0x3::Wallet::Balance",
+                "0x3::Wallet::balance_of",
+                "0x3::Wallet::total_balance",
+            ]
+        );
+
+        let symbols = workspace_symbols(
+            (get_script_path(), MODULE.to_string()),
+            config(),
+            "Signer::addr",
+        );
+        assert_eq!(
+            names(&symbols),
+            vec!["0x1::Signer::address_of", "0x1::Signer::borrow_address"]
+        );
+    }
+}
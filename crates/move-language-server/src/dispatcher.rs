@@ -1,4 +1,7 @@
+use std::collections::HashMap;
 use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use anyhow::Result;
 use crossbeam_channel::Sender;
@@ -11,6 +14,10 @@ use crate::global_state::{GlobalState, GlobalStateSnapshot};
 use crate::main_loop::{on_task, LspError, Task};
 use crate::req;
 
+/// Requests dispatched to the pool, which are not answered or cancelled yet,
+/// along with the flags telling their jobs about the cancellation.
+pub type PendingRequests = HashMap<RequestId, Arc<AtomicBool>>;
+
 fn result_to_task<R>(id: RequestId, result: Result<R::Result>) -> Task
 where
     R: req::Request + 'static,
@@ -39,6 +46,7 @@ pub struct PoolDispatcher<'a> {
     req: Option<Request>,
     pool: &'a ThreadPool,
     global_state: &'a mut GlobalState,
+    pending_requests: &'a mut PendingRequests,
     msg_sender: &'a Sender<Message>,
     task_sender: &'a Sender<Task>,
 }
@@ -48,6 +56,7 @@ impl<'a> PoolDispatcher<'a> {
         req: Request,
         pool: &'a ThreadPool,
         global_state: &'a mut GlobalState,
+        pending_requests: &'a mut PendingRequests,
         msg_sender: &'a Sender<Message>,
         task_sender: &'a Sender<Task>,
     ) -> PoolDispatcher<'a> {
@@ -55,6 +64,7 @@ impl<'a> PoolDispatcher<'a> {
             req: Some(req),
             pool,
             global_state,
+            pending_requests,
             msg_sender,
             task_sender,
         }
@@ -103,10 +113,18 @@ impl<'a> PoolDispatcher<'a> {
             }
         };
 
+        // answered in the main loop, unless cancelled before that
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.pending_requests
+            .insert(id.clone(), Arc::clone(&is_cancelled));
         self.pool.execute({
             let state_snapshot = self.global_state.snapshot();
             let sender = self.task_sender.clone();
             move || {
+                // cancelled request is already answered, it isn't computed if it hasn't started yet
+                if is_cancelled.load(Ordering::SeqCst) {
+                    return;
+                }
                 let result = f(state_snapshot, params);
                 let task = result_to_task::<R>(id, result);
                 sender.send(task).unwrap();
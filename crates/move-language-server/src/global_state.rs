@@ -1,13 +1,14 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crossbeam_channel::{unbounded, Receiver};
-use ra_vfs::{Vfs, VfsChange, VfsTask};
+use ra_vfs::{Vfs, VfsChange, VfsFile, VfsTask};
 
 use crate::fs::ws_root_vfs;
 use analysis::analysis::{Analysis, AnalysisHost};
-use analysis::change::AnalysisChange;
+use analysis::change::{AnalysisChange, InputEdit};
 use analysis::config::Config;
-use utils::leaked_fpath;
+use utils::FileId;
 
 pub struct GlobalStateSnapshot {
     pub config: Config,
@@ -21,6 +22,8 @@ pub struct GlobalState {
     pub analysis_host: AnalysisHost,
     pub vfs: Vfs,
     pub fs_events_receiver: Receiver<VfsTask>,
+    /// Edits of the opened files since the last `load_fs_changes`, for the incremental reparse.
+    pub file_edits: HashMap<FileId, Vec<InputEdit>>,
 }
 
 impl GlobalState {
@@ -42,40 +45,74 @@ impl GlobalState {
             analysis_host,
             vfs,
             fs_events_receiver,
+            file_edits: HashMap::new(),
         }
     }
 
-    pub fn load_fs_changes(&mut self) -> bool {
+    /// Applies the file system changes, returns files which diagnostics might be affected by them.
+    pub fn load_fs_changes(&mut self) -> Vec<FileId> {
         let vfs_changes = self.vfs.commit_changes();
         if vfs_changes.is_empty() {
-            return false;
+            return vec![];
         }
+        // intermediate texts of the file are skipped, edits lead to the latest one
+        let latest_changes: HashMap<VfsFile, usize> = vfs_changes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fs_change)| match fs_change {
+                VfsChange::ChangeFile { file, .. } => Some((*file, i)),
+                _ => None,
+            })
+            .collect();
+
         let mut change = AnalysisChange::new();
-        for fs_change in vfs_changes {
+        let mut changed_files = vec![];
+        for (i, fs_change) in vfs_changes.into_iter().enumerate() {
             match fs_change {
                 VfsChange::AddFile { file, text, .. } => {
-                    let fpath = leaked_fpath(self.vfs.file2path(file).to_str().unwrap());
+                    let fpath = FileId::new(self.vfs.file2path(file).to_str().unwrap());
+                    changed_files.push(fpath);
                     change.add_file(fpath, text.to_string());
                 }
                 VfsChange::ChangeFile { file, text } => {
-                    let path = leaked_fpath(self.vfs.file2path(file).to_str().unwrap());
-                    change.update_file(path, text.to_string());
+                    if latest_changes[&file] != i {
+                        continue;
+                    }
+                    let path = FileId::new(self.vfs.file2path(file).to_str().unwrap());
+                    changed_files.push(path);
+                    match self.file_edits.remove(&path) {
+                        Some(edits) => change.edit_file(path, text.to_string(), edits),
+                        None => change.update_file(path, text.to_string()),
+                    }
                 }
                 VfsChange::RemoveFile { file, path, .. } => {
                     let fpath = path.to_path(self.vfs.file2path(file));
-                    let fpath = leaked_fpath(fpath.to_str().unwrap());
+                    let fpath = FileId::new(fpath.to_str().unwrap());
+                    changed_files.push(fpath);
                     change.remove_file(fpath);
                 }
                 VfsChange::AddRoot { files, .. } => {
                     for (file, _, text) in files {
-                        let fpath = leaked_fpath(self.vfs.file2path(file).to_str().unwrap());
+                        let fpath = FileId::new(self.vfs.file2path(file).to_str().unwrap());
+                        changed_files.push(fpath);
                         change.add_file(fpath, text.to_string());
                     }
                 }
             }
         }
+        // modules might be removed from the changed files, so their previous users are affected too
+        let previous_analysis = self.analysis_host.analysis();
         self.analysis_host.apply_change(change);
-        true
+
+        let mut affected_files = previous_analysis.dependent_files(&changed_files);
+        affected_files.extend(
+            self.analysis_host
+                .analysis()
+                .dependent_files(&changed_files),
+        );
+        affected_files.sort();
+        affected_files.dedup();
+        affected_files
     }
 
     pub fn snapshot(&self) -> GlobalStateSnapshot {
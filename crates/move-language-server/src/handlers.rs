@@ -1,25 +1,122 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
+use lsp_server::ErrorCode;
+use lsp_types::{DocumentHighlight, Location, SymbolInformation, Url};
 
 use crate::global_state::GlobalStateSnapshot;
+use crate::main_loop::LspError;
 use crate::req;
-use analysis::db::FilePosition;
-use utils::leaked_fpath;
+use analysis::db::{FilePosition, FileRange};
+use utils::FileId;
 
-pub fn handle_completion(
-    state_snapshot: GlobalStateSnapshot,
-    params: req::CompletionParams,
-) -> Result<Option<req::CompletionResponse>> {
+fn file_position(params: req::TextDocumentPositionParams) -> FilePosition {
     let req::TextDocumentPositionParams {
         text_document,
         position,
-    } = params.text_document_position;
-    let fpath = leaked_fpath(text_document.uri.to_file_path().unwrap());
-    let pos = (position.line, position.character);
-    let file_position = FilePosition {
+    } = params;
+    let fpath = FileId::new(text_document.uri.to_file_path().unwrap());
+    FilePosition {
         fpath,
-        pos: (pos.0 as usize, pos.1 as usize),
-    };
+        pos: (position.line as usize, position.character as usize),
+    }
+}
+
+fn location(file_range: FileRange) -> Location {
+    let uri = Url::from_file_path(file_range.fpath).unwrap();
+    Location::new(uri, file_range.range)
+}
 
+pub fn handle_completion(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::CompletionParams,
+) -> Result<Option<req::CompletionResponse>> {
+    let file_position = file_position(params.text_document_position);
     let completions = state_snapshot.analysis.completions(file_position);
     Ok(Some(completions.into()))
 }
+
+pub fn handle_hover(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::HoverParams,
+) -> Result<Option<req::Hover>> {
+    let file_position = file_position(params.text_document_position_params);
+    Ok(state_snapshot.analysis.hover(file_position))
+}
+
+pub fn handle_goto_definition(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::GotoDefinitionParams,
+) -> Result<Option<req::GotoDefinitionResponse>> {
+    let file_position = file_position(params.text_document_position_params);
+    let definition = state_snapshot.analysis.goto_definition(file_position);
+    Ok(definition.map(|definition| req::GotoDefinitionResponse::Scalar(location(definition))))
+}
+
+pub fn handle_references(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::ReferenceParams,
+) -> Result<Option<Vec<Location>>> {
+    let file_position = file_position(params.text_document_position);
+    let references = state_snapshot
+        .analysis
+        .find_references(file_position, params.context.include_declaration);
+    Ok(references.map(|references| references.into_iter().map(location).collect()))
+}
+
+pub fn handle_document_highlight(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::DocumentHighlightParams,
+) -> Result<Option<Vec<DocumentHighlight>>> {
+    let file_position = file_position(params.text_document_position_params);
+    let ranges = state_snapshot.analysis.highlight_references(file_position);
+    let highlights = ranges.map(|ranges| {
+        ranges
+            .into_iter()
+            .map(|range| DocumentHighlight { range, kind: None })
+            .collect()
+    });
+    Ok(highlights)
+}
+
+pub fn handle_document_symbol(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::DocumentSymbolParams,
+) -> Result<Option<req::DocumentSymbolResponse>> {
+    let fpath = FileId::new(params.text_document.uri.to_file_path().unwrap());
+    let symbols = state_snapshot.analysis.document_symbols(fpath);
+    Ok(Some(symbols.into()))
+}
+
+pub fn handle_workspace_symbol(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::WorkspaceSymbolParams,
+) -> Result<Option<Vec<SymbolInformation>>> {
+    let symbols = state_snapshot.analysis.workspace_symbols(&params.query);
+    Ok(Some(symbols))
+}
+
+pub fn handle_prepare_rename(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<req::PrepareRenameResponse>> {
+    let file_position = file_position(params);
+    let range = state_snapshot.analysis.prepare_rename(file_position);
+    Ok(range.map(req::PrepareRenameResponse::Range))
+}
+
+pub fn handle_rename(
+    state_snapshot: GlobalStateSnapshot,
+    params: req::RenameParams,
+) -> Result<Option<req::WorkspaceEdit>> {
+    let file_position = file_position(params.text_document_position);
+    let file_edits = state_snapshot
+        .analysis
+        .rename(file_position, &params.new_name)
+        .map_err(|message| LspError::new(ErrorCode::InvalidParams as i32, message))?;
+    let changes: HashMap<_, _> = file_edits
+        .into_iter()
+        .map(|(fpath, edits)| (Url::from_file_path(fpath).unwrap(), edits))
+        .collect();
+    Ok(Some(req::WorkspaceEdit::new(changes)))
+}
@@ -1,18 +1,25 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::iter;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::bail;
 use anyhow::Result;
 use crossbeam_channel::{unbounded, Sender};
-use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
 use lsp_types::notification::{
-    DidChangeConfiguration, DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
-    PublishDiagnostics, ShowMessage,
+    Cancel, DidChangeConfiguration, DidChangeTextDocument, DidCloseTextDocument,
+    DidOpenTextDocument, Progress, PublishDiagnostics, ShowMessage,
 };
-use lsp_types::request::WorkspaceConfiguration;
+use lsp_types::request::{WorkDoneProgressCreate, WorkspaceConfiguration};
 use lsp_types::{
-    ConfigurationItem, ConfigurationParams, Diagnostic, MessageType, PublishDiagnosticsParams,
-    ShowMessageParams, Url,
+    ClientCapabilities, ConfigurationItem, ConfigurationParams, Diagnostic, MessageType,
+    NumberOrString, ProgressParams, ProgressParamsValue, ProgressToken, PublishDiagnosticsParams,
+    ShowMessageParams, Url, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
 };
 use ra_vfs::VfsTask;
 use serde::de::DeserializeOwned;
@@ -20,15 +27,17 @@ use serde::Serialize;
 use threadpool::ThreadPool;
 
 use analysis::analysis::Analysis;
+use analysis::change::apply_text_change;
 use analysis::config::Config;
 
-use crate::dispatcher::PoolDispatcher;
+use crate::dispatcher::{PendingRequests, PoolDispatcher};
 use crate::global_state::{initialize_new_global_state, GlobalState};
 use crate::handlers;
 use crate::req;
 use crate::subscriptions::OpenedFiles;
-use analysis::db::FileDiagnostic;
-use utils::{leaked_fpath, MoveFilePath};
+use analysis::db::{group_by_file, FileDiagnostic};
+use analysis::query::Revision;
+use utils::FileId;
 
 #[derive(Debug)]
 pub struct LspError {
@@ -59,13 +68,19 @@ impl Error for LspError {}
 #[derive(Debug)]
 pub enum Task {
     Respond(Response),
-    Diagnostic(Vec<FileDiagnostic>),
+    /// Diagnostics of the check of the file, computed from the analysis at the revision.
+    /// Besides the errors of the file itself, they might contain the ones of its dependencies.
+    Diagnostic(Revision, FileId, Vec<FileDiagnostic>),
+    /// Check of the file is finished or skipped.
+    FileChecked,
 }
 
 pub enum Event {
     Task(Task),
     Vfs(VfsTask),
     Msg(Message),
+    /// Debounce window of the pending diagnostics is over.
+    DiagnosticsDue,
 }
 
 impl fmt::Debug for Event {
@@ -86,20 +101,34 @@ impl fmt::Debug for Event {
             Event::Msg(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
             Event::Task(it) => fmt::Debug::fmt(it, f),
+            Event::DiagnosticsDue => f.write_str("DiagnosticsDue"),
         }
     }
 }
 
-pub fn main_loop(global_state: &mut GlobalState, connection: &Connection) -> Result<()> {
+pub fn main_loop(
+    global_state: &mut GlobalState,
+    connection: &Connection,
+    client_caps: &ClientCapabilities,
+) -> Result<()> {
     log::info!("starting example main loop");
 
-    let pool = ThreadPool::new(1);
+    let mut pool = match global_state.config.worker_threads {
+        Some(threads) => ThreadPool::new(threads),
+        None => ThreadPool::default(),
+    };
     let (task_sender, task_receiver) = unbounded::<Task>();
 
-    let mut loop_state = LoopState::default();
+    let mut loop_state = LoopState::new(client_caps);
 
     log::info!("server initialized, serving requests");
     loop {
+        let diagnostics_timer = match loop_state.diagnostics_deadline {
+            Some(deadline) => {
+                crossbeam_channel::after(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => crossbeam_channel::never(),
+        };
         let event = crossbeam_channel::select! {
             recv(&connection.receiver) -> message => match message {
                 Ok(message) => Event::Msg(message),
@@ -109,7 +138,8 @@ pub fn main_loop(global_state: &mut GlobalState, connection: &Connection) -> Res
             recv(global_state.fs_events_receiver) -> task => match task {
                 Ok(task) => Event::Vfs(task),
                 Err(_) => bail!("vfs died"),
-            }
+            },
+            recv(diagnostics_timer) -> _ => Event::DiagnosticsDue,
         };
         if let Event::Msg(Message::Request(req)) = &event {
             if connection.handle_shutdown(&req)? {
@@ -117,7 +147,7 @@ pub fn main_loop(global_state: &mut GlobalState, connection: &Connection) -> Res
             }
         }
         loop_turn(
-            &pool,
+            &mut pool,
             &task_sender,
             &connection,
             global_state,
@@ -133,9 +163,114 @@ pub struct LoopState {
     next_request_id: u64,
     opened_files: OpenedFiles,
     configuration_request_id: Option<RequestId>,
+    pending_requests: PendingRequests,
+    diagnostics_revisions: DiagnosticsRevisions,
+    reported_diagnostics: ReportedDiagnostics,
+    /// Files to check when the debounce window is over.
+    pending_diagnostics: HashSet<FileId>,
+    diagnostics_deadline: Option<Instant>,
+    /// Client shows the progress created by the server.
+    work_done_progress: bool,
+    /// `window/workDoneProgress/create` request, which is not answered yet.
+    progress_request_id: Option<RequestId>,
+    diagnostics_progress: Option<DiagnosticsProgress>,
+}
+
+const DIAGNOSTICS_PROGRESS_TOKEN: &str = "move/diagnostics";
+
+/// Number of the files checked out of the ones scheduled since the progress began.
+/// Progress is reported only after the client creates it.
+#[derive(Debug)]
+struct DiagnosticsProgress {
+    checked: usize,
+    total: usize,
+    is_created: bool,
+}
+
+impl DiagnosticsProgress {
+    fn message(&self) -> String {
+        format!("{}/{} files", self.checked, self.total)
+    }
+
+    fn percentage(&self) -> f64 {
+        self.checked as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// Latest revision of the analysis the diagnostics of the file are requested at.
+/// Checks of the outdated revisions are skipped, and their late results are dropped.
+#[derive(Debug, Default, Clone)]
+pub struct DiagnosticsRevisions(Arc<Mutex<HashMap<FileId, Revision>>>);
+
+impl DiagnosticsRevisions {
+    pub fn request(&self, fpath: FileId, revision: Revision) {
+        self.0.lock().unwrap().insert(fpath, revision);
+    }
+
+    pub fn is_latest(&self, fpath: FileId, revision: Revision) -> bool {
+        match self.0.lock().unwrap().get(&fpath) {
+            Some(latest) => *latest <= revision,
+            None => true,
+        }
+    }
+}
+
+/// Latest diagnostics of the checks, by the checked file. Publishing replaces all the
+/// diagnostics of the file, so the ones reported into the same file by different checks
+/// are merged before publishing. Check of the file replaces what the other checks
+/// reported into it, as those are not rechecked after the file changes.
+#[derive(Debug, Default)]
+struct ReportedDiagnostics(HashMap<FileId, Vec<FileDiagnostic>>);
+
+impl ReportedDiagnostics {
+    /// Replaces the diagnostics of the check of `checked`,
+    /// returns the merged diagnostics of every file they change.
+    fn update(
+        &mut self,
+        checked: FileId,
+        file_diagnostics: Vec<FileDiagnostic>,
+    ) -> Vec<FileDiagnostic> {
+        let mut changed_files: Vec<FileId> = vec![checked];
+        for (other_checked, reported) in self.0.iter_mut() {
+            if *other_checked != checked {
+                reported.retain(|file_diagnostic| file_diagnostic.fpath != checked);
+            }
+        }
+        let previous = self.0.insert(checked, file_diagnostics).unwrap_or_default();
+        for file_diagnostic in self.0[&checked].iter().chain(previous.iter()) {
+            if !changed_files.contains(&file_diagnostic.fpath) {
+                changed_files.push(file_diagnostic.fpath);
+            }
+        }
+        changed_files
+            .into_iter()
+            .map(|fpath| self.merged(fpath))
+            .collect()
+    }
+
+    fn merged(&self, fpath: FileId) -> FileDiagnostic {
+        let reported = self
+            .0
+            .values()
+            .flatten()
+            .filter(|file_diagnostic| file_diagnostic.fpath == fpath)
+            .cloned();
+        group_by_file(iter::once(FileDiagnostic::new_empty(fpath)).chain(reported)).remove(0)
+    }
 }
 
 impl LoopState {
+    pub fn new(client_caps: &ClientCapabilities) -> Self {
+        LoopState {
+            work_done_progress: client_caps
+                .window
+                .as_ref()
+                .and_then(|window| window.work_done_progress)
+                .unwrap_or(false),
+            ..LoopState::default()
+        }
+    }
+
     fn next_request_id(&mut self) -> RequestId {
         self.next_request_id += 1;
         self.next_request_id.into()
@@ -150,7 +285,7 @@ impl LoopState {
 }
 
 pub fn loop_turn(
-    pool: &ThreadPool,
+    pool: &mut ThreadPool,
     task_sender: &Sender<Task>,
     connection: &Connection,
     global_state: &mut GlobalState,
@@ -160,12 +295,44 @@ pub fn loop_turn(
     if matches!(event, Event::Msg(_)) {
         log::info!("loop turn = {:#?}", &event);
     }
+    let is_edit = matches!(&event, Event::Msg(Message::Notification(not))
+        if notification_is::<DidChangeTextDocument>(not));
     match event {
-        Event::Task(task) => on_task(task, &connection.sender),
+        Event::Task(task) => match task {
+            Task::Respond(response) => {
+                // cancelled request is already answered
+                if loop_state.pending_requests.remove(&response.id).is_some() {
+                    on_task(Task::Respond(response), &connection.sender);
+                }
+            }
+            Task::Diagnostic(revision, checked, file_diagnostics) => {
+                if loop_state
+                    .diagnostics_revisions
+                    .is_latest(checked, revision)
+                {
+                    let file_diagnostics = loop_state
+                        .reported_diagnostics
+                        .update(checked, file_diagnostics);
+                    on_task(
+                        Task::Diagnostic(revision, checked, file_diagnostics),
+                        &connection.sender,
+                    );
+                }
+            }
+            Task::FileChecked => on_file_checked(loop_state, &connection.sender),
+        },
         Event::Vfs(vfs_task) => global_state.vfs.handle_task(vfs_task),
+        Event::DiagnosticsDue => (),
         Event::Msg(message) => match message {
             Message::Request(req) => {
-                on_request(global_state, pool, task_sender, &connection.sender, req)?;
+                on_request(
+                    global_state,
+                    &mut loop_state.pending_requests,
+                    pool,
+                    task_sender,
+                    &connection.sender,
+                    req,
+                )?;
             }
             Message::Notification(not) => {
                 on_notification(&connection.sender, global_state, loop_state, not)?;
@@ -184,47 +351,231 @@ pub fn loop_turn(
                             if let Some(new_config) = configs.get(0) {
                                 let mut config = Config::default();
                                 config.update(new_config);
-                                *global_state = initialize_new_global_state(
+                                if let Some(threads) = config.worker_threads {
+                                    pool.set_num_threads(threads);
+                                }
+                                let mut new_global_state = initialize_new_global_state(
                                     global_state.ws_root.clone(),
                                     config,
                                 );
+                                // checks requested before the reload are outdated
+                                new_global_state
+                                    .analysis_host
+                                    .continue_revisions(&global_state.analysis_host);
+                                *global_state = new_global_state;
                             }
                         }
                         (None, None) => {
                             log::error!("received empty server settings response from the client")
                         }
                     }
+                } else if Some(&resp.id) == loop_state.progress_request_id.as_ref() {
+                    loop_state.progress_request_id = None;
+                    on_progress_created(loop_state, resp, &connection.sender);
                 }
             }
         },
     }
-    let fs_state_changed = global_state.load_fs_changes();
-    if fs_state_changed {
+    let affected_files = global_state.load_fs_changes();
+    if !affected_files.is_empty() {
         log::info!("fs_state_changed = true, reextract diagnostics");
         let analysis = global_state.analysis_host.analysis();
 
-        let mut files = vec![];
-        files.extend(loop_state.opened_files.files());
-        files.extend(analysis.db().module_files().keys());
-
-        let cloned_task_sender = task_sender.clone();
-        pool.execute(move || compute_file_diagnostics(analysis, cloned_task_sender, files));
-        // compute_file_diagnostics(pool, analysis, task_sender.clone(), files);
+        let opened_files = loop_state.opened_files.files();
+        // diagnostics of the removed files are cleared
+        loop_state
+            .pending_diagnostics
+            .extend(affected_files.into_iter().filter(|fpath| {
+                opened_files.contains(fpath)
+                    || analysis.db().is_fpath_for_a_module(*fpath)
+                    || analysis.db().file_text(*fpath).is_none()
+            }));
+        // every edit postpones the check, other changes are checked with the pending ones
+        let delay = if is_edit {
+            global_state.config.diagnostics_debounce
+        } else {
+            Duration::from_millis(0)
+        };
+        let deadline = Instant::now() + delay;
+        loop_state.diagnostics_deadline = match loop_state.diagnostics_deadline {
+            Some(pending) if pending > deadline => Some(pending),
+            _ => Some(deadline),
+        };
+    }
+    match loop_state.diagnostics_deadline {
+        Some(deadline) if deadline <= Instant::now() => {
+            loop_state.diagnostics_deadline = None;
+            let mut files: Vec<_> = loop_state.pending_diagnostics.drain().collect();
+            files.sort();
+            schedule_diagnostics(
+                pool,
+                task_sender,
+                connection,
+                global_state,
+                loop_state,
+                files,
+            );
+        }
+        _ => (),
     }
     Ok(())
 }
 
+fn schedule_diagnostics(
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    connection: &Connection,
+    global_state: &GlobalState,
+    loop_state: &mut LoopState,
+    files: Vec<FileId>,
+) {
+    if files.is_empty() {
+        return;
+    }
+    if loop_state.work_done_progress {
+        start_diagnostics_progress(loop_state, files.len(), &connection.sender);
+    }
+
+    // files are checked in parallel, each one is skipped if it changes again before the check starts
+    let revision = global_state.analysis_host.analysis().db().revision();
+    for fpath in files {
+        loop_state.diagnostics_revisions.request(fpath, revision);
+        let analysis = global_state.analysis_host.analysis();
+        let task_sender = task_sender.clone();
+        let diagnostics_revisions = loop_state.diagnostics_revisions.clone();
+        pool.execute(move || {
+            if diagnostics_revisions.is_latest(fpath, revision) {
+                compute_file_diagnostics(analysis, task_sender.clone(), vec![fpath]);
+            }
+            task_sender.send(Task::FileChecked).unwrap();
+        });
+    }
+}
+
+fn start_diagnostics_progress(
+    loop_state: &mut LoopState,
+    files_count: usize,
+    msg_sender: &Sender<Message>,
+) {
+    // files scheduled during the running check extend its progress
+    if let Some(progress) = &mut loop_state.diagnostics_progress {
+        progress.total += files_count;
+        if progress.is_created {
+            let report = WorkDoneProgress::Report(WorkDoneProgressReport {
+                cancellable: None,
+                message: Some(progress.message()),
+                percentage: Some(progress.percentage()),
+            });
+            send_progress(report, msg_sender);
+        }
+        return;
+    }
+
+    // progress begins once the client answers the request
+    let request_id = loop_state.next_request_id();
+    let request = request_new::<WorkDoneProgressCreate>(
+        request_id.clone(),
+        WorkDoneProgressCreateParams {
+            token: diagnostics_progress_token(),
+        },
+    );
+    msg_sender.send(request.into()).unwrap();
+    loop_state.progress_request_id = Some(request_id);
+    loop_state.diagnostics_progress = Some(DiagnosticsProgress {
+        checked: 0,
+        total: files_count,
+        is_created: false,
+    });
+}
+
+fn on_progress_created(loop_state: &mut LoopState, resp: Response, msg_sender: &Sender<Message>) {
+    if let Some(err) = resp.error {
+        log::error!("client failed to create the progress: {:?}", err);
+        loop_state.work_done_progress = false;
+        loop_state.diagnostics_progress = None;
+        return;
+    }
+    // checks might be over before the answer
+    let progress = match &mut loop_state.diagnostics_progress {
+        Some(progress) => progress,
+        None => return,
+    };
+    progress.is_created = true;
+    let begin = WorkDoneProgress::Begin(WorkDoneProgressBegin {
+        title: "Checking".to_string(),
+        cancellable: None,
+        message: Some(progress.message()),
+        percentage: Some(progress.percentage()),
+    });
+    send_progress(begin, msg_sender);
+}
+
+fn on_file_checked(loop_state: &mut LoopState, msg_sender: &Sender<Message>) {
+    let progress = match &mut loop_state.diagnostics_progress {
+        Some(progress) => progress,
+        None => return,
+    };
+    progress.checked += 1;
+    if progress.checked < progress.total {
+        if progress.is_created {
+            let report = WorkDoneProgress::Report(WorkDoneProgressReport {
+                cancellable: None,
+                message: Some(progress.message()),
+                percentage: Some(progress.percentage()),
+            });
+            send_progress(report, msg_sender);
+        }
+    } else {
+        if progress.is_created {
+            let end = WorkDoneProgress::End(WorkDoneProgressEnd {
+                message: Some(progress.message()),
+            });
+            send_progress(end, msg_sender);
+        }
+        loop_state.diagnostics_progress = None;
+    }
+}
+
+fn diagnostics_progress_token() -> ProgressToken {
+    NumberOrString::String(DIAGNOSTICS_PROGRESS_TOKEN.to_string())
+}
+
+fn send_progress(progress: WorkDoneProgress, msg_sender: &Sender<Message>) {
+    let params = ProgressParams {
+        token: diagnostics_progress_token(),
+        value: ProgressParamsValue::WorkDone(progress),
+    };
+    msg_sender
+        .send(notification_new::<Progress>(params).into())
+        .unwrap();
+}
+
 fn on_request(
     global_state: &mut GlobalState,
+    pending_requests: &mut PendingRequests,
     pool: &ThreadPool,
     task_sender: &Sender<Task>,
     msg_sender: &Sender<Message>,
     req: Request,
 ) -> Result<()> {
-    let mut pool_dispatcher =
-        PoolDispatcher::new(req, pool, global_state, msg_sender, task_sender);
+    let mut pool_dispatcher = PoolDispatcher::new(
+        req,
+        pool,
+        global_state,
+        pending_requests,
+        msg_sender,
+        task_sender,
+    );
     pool_dispatcher
         .on::<req::Completion>(handlers::handle_completion)?
+        .on::<req::HoverRequest>(handlers::handle_hover)?
+        .on::<req::GotoDefinition>(handlers::handle_goto_definition)?
+        .on::<req::References>(handlers::handle_references)?
+        .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
+        .on::<req::DocumentSymbolRequest>(handlers::handle_document_symbol)?
+        .on::<req::WorkspaceSymbol>(handlers::handle_workspace_symbol)?
+        .on::<req::PrepareRenameRequest>(handlers::handle_prepare_rename)?
+        .on::<req::Rename>(handlers::handle_rename)?
         .finish();
     Ok(())
 }
@@ -245,14 +596,12 @@ pub fn on_task(task: Task, msg_sender: &Sender<Message>) {
         Task::Respond(response) => {
             msg_sender.send(response.into()).unwrap();
         }
-        Task::Diagnostic(file_diags) => {
+        Task::FileChecked => (),
+        Task::Diagnostic(_, _, file_diags) => {
             for file_diag in file_diags {
                 let uri = Url::from_file_path(file_diag.fpath).unwrap();
 
-                let mut diagnostics = vec![];
-                if file_diag.diagnostic.is_some() {
-                    diagnostics.push(file_diag.diagnostic.unwrap());
-                }
+                let diagnostics = file_diag.diagnostics;
                 log::info!(
                     "Send diagnostic for file {:?}: {:#?}",
                     file_diag.fpath,
@@ -290,32 +639,68 @@ fn on_notification(
                 "Cannot find file {:?} in current roots",
                 &fpath
             );
-            loop_state
-                .opened_files
-                .add(leaked_fpath(fpath.to_str().unwrap()));
+            let fpath = FileId::new(fpath.to_str().unwrap());
+            // opened text is parsed from scratch
+            global_state.file_edits.remove(&fpath);
+            loop_state.opened_files.add(fpath);
             return Ok(());
         }
         Err(not) => not,
     };
     let not = match notification_cast::<DidChangeTextDocument>(not) {
-        Ok(mut params) => {
+        Ok(params) => {
             let uri = params.text_document.uri;
             let fpath = uri
                 .to_file_path()
                 .map_err(|_| anyhow::anyhow!("invalid uri: {}", uri))?;
-            let new_text = params
-                .content_changes
-                .pop()
-                .ok_or_else(|| anyhow::anyhow!("empty changes".to_string()))?
-                .text;
+            let content_changes = params.content_changes;
+            if content_changes.is_empty() {
+                log::warn!("Change notification for {:?} has no changes", fpath);
+                return Ok(());
+            }
+            let mut edits = vec![];
+            let mut invalid_range = None;
             global_state
                 .vfs
                 .change_file_overlay(fpath.as_path(), |text| {
-                    *text = new_text;
+                    // changes are applied to a copy, so that the invalid one drops the whole notification
+                    let mut changed_text = text.clone();
+                    for change in content_changes {
+                        // vfs keeps the text with normalized line endings
+                        let new_text = change.text.replace("\r\n", "\n");
+                        match apply_text_change(&mut changed_text, change.range, &new_text) {
+                            Some(edit) => edits.push(edit),
+                            None => {
+                                invalid_range = Some(change.range);
+                                return;
+                            }
+                        }
+                    }
+                    *text = changed_text;
                 });
-            loop_state
-                .opened_files
-                .add(leaked_fpath(fpath.to_str().unwrap()));
+            if let Some(range) = invalid_range {
+                log::error!(
+                    "Invalid change range {:?} for {:?}, changes are dropped",
+                    range,
+                    fpath
+                );
+                show_message(
+                    MessageType::Warning,
+                    format!(
+                        "Changes of {:?} are out of sync with the server, reopen the file",
+                        fpath
+                    ),
+                    msg_sender,
+                );
+                return Ok(());
+            }
+            let fpath = FileId::new(fpath.to_str().unwrap());
+            global_state
+                .file_edits
+                .entry(fpath)
+                .or_default()
+                .extend(edits);
+            loop_state.opened_files.add(fpath);
             return Ok(());
         }
         Err(not) => not,
@@ -328,7 +713,7 @@ fn on_notification(
                 .map_err(|_| anyhow::anyhow!("invalid uri: {}", uri))?;
             loop_state
                 .opened_files
-                .remove(leaked_fpath(fpath.to_str().unwrap()));
+                .remove(FileId::new(fpath.to_str().unwrap()));
             return Ok(());
         }
         Err(not) => not,
@@ -356,6 +741,26 @@ fn on_notification(
         }
         Err(not) => not,
     };
+    let not = match notification_cast::<Cancel>(not) {
+        Ok(params) => {
+            let id: RequestId = match params.id {
+                NumberOrString::Number(id) => id.into(),
+                NumberOrString::String(id) => id.into(),
+            };
+            // result of the request is dropped when it arrives
+            if let Some(is_cancelled) = loop_state.pending_requests.remove(&id) {
+                is_cancelled.store(true, Ordering::SeqCst);
+                let response = Response::new_err(
+                    id,
+                    ErrorCode::RequestCanceled as i32,
+                    "canceled by client".to_string(),
+                );
+                msg_sender.send(response.into())?;
+            }
+            return Ok(());
+        }
+        Err(not) => not,
+    };
     if not.method.starts_with("$/") {
         return Ok(());
     }
@@ -365,26 +770,62 @@ fn on_notification(
 pub fn compute_file_diagnostics(
     analysis: Analysis,
     task_sender: Sender<Task>,
-    files: Vec<MoveFilePath>,
+    files: Vec<FileId>,
 ) {
     log::info!("Computing diagnostics for files: {:#?}", files);
-    let mut diagnostics = vec![];
+    let revision = analysis.db().revision();
+    // syntax errors are published right away, and merged into the compiler parse error later
+    let mut syntax_diagnostics = HashMap::new();
+    for fpath in &files {
+        if analysis.db().file_text(*fpath).is_some() {
+            let file_diagnostic = analysis.check_file_syntax(*fpath);
+            task_sender
+                .send(Task::Diagnostic(
+                    revision,
+                    *fpath,
+                    vec![file_diagnostic.clone()],
+                ))
+                .unwrap();
+            syntax_diagnostics.insert(*fpath, file_diagnostic);
+        }
+    }
+
     for fpath in files {
         // clear previous diagnostics for file
-        diagnostics.push(FileDiagnostic::new_empty(fpath));
-
-        let text = match analysis.db().available_files.get(fpath) {
-            Some(text) => text,
-            None => {
-                log::warn!("Trying to check untracked file: {:?}", fpath);
-                continue;
+        let mut diagnostics = vec![FileDiagnostic::new_empty(fpath)];
+        match analysis.db().file_text(fpath) {
+            Some(text) => diagnostics.extend(analysis.check_file_with_compiler(fpath, text)),
+            None => log::warn!("Trying to check untracked file: {:?}", fpath),
+        }
+        let mut diagnostics = group_by_file(diagnostics);
+        // parser of the dialect knows the syntax extensions, like address templates
+        if analysis.db().parsed_file(fpath).is_err() {
+            if let Some(file_syntax_diagnostics) = syntax_diagnostics.remove(&fpath) {
+                merge_syntax_diagnostics(&mut diagnostics[0], file_syntax_diagnostics);
             }
-        };
-        if let Some(d) = analysis.check_file_with_compiler(fpath, text) {
-            diagnostics.push(d);
         }
+        task_sender
+            .send(Task::Diagnostic(revision, fpath, diagnostics))
+            .unwrap();
     }
-    task_sender.send(Task::Diagnostic(diagnostics)).unwrap();
+}
+
+/// Adds the syntax errors the compiler doesn't report. Compiler stops at the first parse error,
+/// the syntax errors up to it are the same error, located by tree-sitter differently.
+fn merge_syntax_diagnostics(file_diagnostic: &mut FileDiagnostic, syntax: FileDiagnostic) {
+    let reported_until = file_diagnostic
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.range.end)
+        .max();
+    let not_reported = syntax
+        .diagnostics
+        .into_iter()
+        .filter(|diagnostic| match reported_until {
+            Some(end) => diagnostic.range.start > end,
+            None => true,
+        });
+    file_diagnostic.diagnostics.extend(not_reported);
 }
 
 pub fn notification_cast<N>(notification: Notification) -> Result<N::Params, Notification>
@@ -2,7 +2,11 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use lsp_server::{Connection, ProtocolError};
-use lsp_types::{ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind};
+use lsp_types::{
+    ClientCapabilities, HoverProviderCapability, RenameOptions, RenameProviderCapability,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    WorkDoneProgressOptions,
+};
 use serde::de::DeserializeOwned;
 
 use analysis::config::Config;
@@ -12,7 +16,19 @@ use crate::main_loop;
 
 fn move_language_server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::Full)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::Incremental,
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(true),
+        references_provider: Some(true),
+        document_highlight_provider: Some(true),
+        document_symbol_provider: Some(true),
+        workspace_symbol_provider: Some(true),
+        rename_provider: Some(RenameProviderCapability::Options(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        })),
         ..ServerCapabilities::default()
     }
 }
@@ -21,7 +37,9 @@ pub fn initialize_server(connection: &Connection) -> Result<serde_json::Value, P
     connection.initialize(serde_json::to_value(move_language_server_capabilities()).unwrap())
 }
 
-pub fn parse_initialize_params(init_params: serde_json::Value) -> Result<(PathBuf, Config)> {
+pub fn parse_initialize_params(
+    init_params: serde_json::Value,
+) -> Result<(PathBuf, Config, ClientCapabilities)> {
     let initialize_params =
         from_json::<lsp_types::InitializeParams>("InitializeParams", init_params)?;
     if let Some(client_info) = initialize_params.client_info {
@@ -42,16 +60,16 @@ pub fn parse_initialize_params(init_params: serde_json::Value) -> Result<(PathBu
     if let Some(value) = &initialize_params.initialization_options {
         config.update(value);
     }
-    Ok((root, config))
+    Ok((root, config, initialize_params.capabilities))
 }
 
 pub fn run_server(connection: &Connection) -> Result<()> {
     let init_params = initialize_server(connection)?;
-    let (ws_root, config) = parse_initialize_params(init_params)?;
+    let (ws_root, config, client_caps) = parse_initialize_params(init_params)?;
     log::info!("Initialization is finished");
 
     let mut global_state = initialize_new_global_state(ws_root, config);
-    main_loop::main_loop(&mut global_state, connection)
+    main_loop::main_loop(&mut global_state, connection, &client_caps)
 }
 
 pub fn from_json<T: DeserializeOwned>(what: &'static str, json: serde_json::Value) -> Result<T> {
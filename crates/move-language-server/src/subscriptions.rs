@@ -2,21 +2,21 @@
 //! which we want to publish diagnostics, syntax highlighting, etc.
 
 use std::collections::HashSet;
-use utils::MoveFilePath;
+use utils::FileId;
 
 #[derive(Debug, Default, Clone)]
 pub struct OpenedFiles {
-    files: HashSet<MoveFilePath>,
+    files: HashSet<FileId>,
 }
 
 impl OpenedFiles {
-    pub fn add(&mut self, fpath: MoveFilePath) {
+    pub fn add(&mut self, fpath: FileId) {
         self.files.insert(fpath);
     }
-    pub fn remove(&mut self, fpath: MoveFilePath) {
+    pub fn remove(&mut self, fpath: FileId) {
         self.files.remove(&fpath);
     }
-    pub fn files(&self) -> Vec<MoveFilePath> {
+    pub fn files(&self) -> Vec<FileId> {
         self.files.iter().copied().collect()
     }
 }
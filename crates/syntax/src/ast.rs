@@ -1,6 +1,6 @@
 use core::fmt;
 
-use tree_sitter::Node;
+use tree_sitter::{InputEdit, Node};
 //
 // pub trait AstNode {
 //     fn can_cast(kind: &str) -> bool
@@ -47,6 +47,10 @@ macro_rules! define_ast_node {
                 Self { source, node }
             }
 
+            pub fn text(&self) -> &'a str {
+                self.node.utf8_text(self.source.as_bytes()).unwrap()
+            }
+
             pub fn is_position_inside(&self, pos: (usize, usize)) -> bool {
                 let point = tree_sitter::Point::new(pos.0, pos.1);
                 let node_range = self.node.range();
@@ -114,6 +118,7 @@ macro_rules! define_proxy_array_named_field {
                 .map(|node| {
                     let mut cursor = self.node.walk();
                     node.named_children(&mut cursor)
+                        .filter(|node| !is_trivia(node))
                         .map(|node| $ast_type::new(self.source, node))
                         .collect()
                 })
@@ -126,7 +131,7 @@ macro_rules! define_type_field {
         pub fn $field_name(&self) -> Option<Type> {
             self.node
                 .child_by_field_name("type")
-                .map(|node| Type::new(self.source, node))
+                .and_then(|node| Type::cast(self.source, node))
         }
     };
 }
@@ -142,17 +147,27 @@ macro_rules! define_enum {
 
         impl<'a> $enum_name<'a> {
             pub fn new(source: &'a str, node: Node<'a>) -> Self {
+                Self::cast(source, node).unwrap_or_else(|| unreachable!())
+            }
+
+            /// `None` for nodes of other kinds, like the ones of error-recovered trees.
+            pub fn cast(source: &'a str, node: Node<'a>) -> Option<Self> {
                 match node.kind() {
                     $(
-                        stringify!($node_ident) => $enum_name::$ast_type($ast_type::new(source, node)),
+                        stringify!($node_ident) => Some($enum_name::$ast_type($ast_type::new(source, node))),
                     )*
-                    _ => unreachable!(),
+                    _ => None,
                 }
             }
         }
     }
 }
 
+/// Comments and error nodes, which could appear anywhere in the tree.
+pub fn is_trivia(node: &Node) -> bool {
+    node.kind() == "line_comment" || node.is_error()
+}
+
 #[derive(Clone)]
 pub struct SourceFile {
     source: String,
@@ -179,12 +194,42 @@ impl SourceFile {
         Self { tree, source }
     }
 
+    /// Reparses the file after `edits`, reusing unchanged parts of the current tree.
+    /// `edits` are applied in order and together turn the current source into `new_source`.
+    pub fn edit(&self, new_source: String, edits: &[InputEdit]) -> Self {
+        let mut old_tree = self.tree.clone();
+        for edit in edits {
+            old_tree.edit(edit);
+        }
+        let tree = crate::parser().parse(&new_source, Some(&old_tree)).unwrap();
+        Self {
+            tree,
+            source: new_source,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
     pub fn definition(&self) -> Option<Definition> {
-        self.tree
-            .root_node()
-            .named_child(0)
-            .filter(|node| node.kind() != "ERROR")
+        let root = self.tree.root_node();
+        let mut cursor = root.walk();
+        let first_node = root
+            .named_children(&mut cursor)
+            .find(|node| node.kind() != "line_comment");
+        first_node
+            .filter(|node| Definition::can_cast(node.kind()))
+            .map(|node| Definition::new(&self.source, node))
+    }
+
+    pub fn definitions(&self) -> Vec<Definition> {
+        let root = self.tree.root_node();
+        let mut cursor = root.walk();
+        root.named_children(&mut cursor)
+            .filter(|node| Definition::can_cast(node.kind()))
             .map(|node| Definition::new(&self.source, node))
+            .collect()
     }
 }
 
@@ -196,6 +241,10 @@ pub enum Definition<'a> {
 }
 
 impl<'a> Definition<'a> {
+    pub fn can_cast(kind: &str) -> bool {
+        matches!(kind, "script_block" | "module_definition" | "address_block")
+    }
+
     pub fn new(source: &'a str, node: Node<'a>) -> Self {
         match node.kind() {
             "script_block" => Definition::ScriptBlock(ScriptBlock::new(source, node)),
@@ -239,11 +288,38 @@ impl<'a> Module<'a> {
     define_proxy_array_named_field!(body, ModuleItem);
 }
 
-define_ast_node!(UseDecl, [address, module]);
+define_ast_node!(UseDecl, [address, module, alias, members]);
 
 impl<'a> UseDecl<'a> {
     define_named_ident_literal!(address);
     define_named_ident_literal!(module);
+
+    pub fn alias(&self) -> Option<&str> {
+        self.node
+            .child_by_field_name("as")
+            .map(|node| node.utf8_text(self.source.as_bytes()).unwrap())
+    }
+
+    pub fn members(&self) -> Vec<UseMember> {
+        let mut cursor = self.node.walk();
+        self.node
+            .named_children(&mut cursor)
+            .filter(|&node| node.kind() == "use_member")
+            .map(|node| UseMember::new(self.source, node))
+            .collect()
+    }
+}
+
+define_ast_node!(UseMember, [member, alias]);
+
+impl<'a> UseMember<'a> {
+    define_named_ident_literal!(member);
+
+    pub fn alias(&self) -> Option<&str> {
+        self.node
+            .child_by_field_name("as")
+            .map(|node| node.utf8_text(self.source.as_bytes()).unwrap())
+    }
 }
 
 define_ast_node!(FieldDef, [field, typ]);
@@ -280,6 +356,7 @@ pub enum ModuleItem<'a> {
     NativeFuncDef(NativeFuncDef<'a>),
     Struct(StructDef<'a>),
     NativeStruct(NativeStructDef<'a>),
+    Spec(SpecBlock<'a>),
 }
 
 impl<'a> ModuleItem<'a> {
@@ -294,11 +371,14 @@ impl<'a> ModuleItem<'a> {
             "native_struct_definition" => {
                 ModuleItem::NativeStruct(NativeStructDef::new(source, node))
             }
+            "spec_block" => ModuleItem::Spec(SpecBlock::new(source, node)),
             _ => unreachable!(),
         }
     }
 }
 
+define_ast_node!(SpecBlock, []);
+
 define_ast_node!(
     FuncDef,
     [name, type_parameters, params, return_type, acquires, body]
@@ -336,6 +416,7 @@ impl<'a> Block<'a> {
         let mut cursor = self.node.walk();
         self.node
             .named_children(&mut cursor)
+            .filter(|node| !is_trivia(node))
             .map(|node| BlockItem::new(self.source, node))
             .collect()
     }
@@ -405,7 +486,14 @@ impl<'a> ApplyType<'a> {
 define_ast_node!(RefType, [typ]);
 
 impl<'a> RefType<'a> {
-    define_field_from_first_child!(typ, Type);
+    pub fn typ(&self) -> Option<Type> {
+        let mut cursor = self.node.walk();
+        let typ = self
+            .node
+            .named_children(&mut cursor)
+            .find_map(|node| Type::cast(self.source, node));
+        typ
+    }
 }
 
 define_ast_node!(TupleType, [items]);
@@ -415,7 +503,7 @@ impl<'a> TupleType<'a> {
         let mut cursor = self.node.walk();
         self.node
             .named_children(&mut cursor)
-            .map(|node| Type::new(self.source, node))
+            .filter_map(|node| Type::cast(self.source, node))
             .collect()
     }
 }
@@ -540,9 +628,17 @@ impl<'a> NotExpr<'a> {
     define_named_field!(exp, UnaryExpr);
 }
 
-define_ast_node!(BorrowExpr, [exp]);
+define_ast_node!(BorrowExpr, [is_mut, exp]);
 
 impl<'a> BorrowExpr<'a> {
+    /// `&mut` borrow.
+    pub fn is_mut(&self) -> bool {
+        self.node
+            .child(0)
+            .map(|node| node.kind() == "&mut")
+            .unwrap_or(false)
+    }
+
     define_named_field!(exp, UnaryExpr);
 }
 
@@ -571,6 +667,11 @@ pub enum Term<'a> {
     Unit(UnitExpr<'a>),
     ExprList(ExprList<'a>),
     Block(Block<'a>),
+    Dot(DotExpr<'a>),
+    Index(IndexExpr<'a>),
+    Cast(CastExpr<'a>),
+    Annotate(AnnotateExpr<'a>),
+    Spec(SpecBlock<'a>),
 }
 
 impl<'a> Term<'a> {
@@ -584,6 +685,11 @@ impl<'a> Term<'a> {
             "unit_expression" => Term::Unit(UnitExpr::new(source, node)),
             "expression_list" => Term::ExprList(ExprList::new(source, node)),
             "block" => Term::Block(Block::new(source, node)),
+            "dot_expression" => Term::Dot(DotExpr::new(source, node)),
+            "index_expression" => Term::Index(IndexExpr::new(source, node)),
+            "cast_expression" => Term::Cast(CastExpr::new(source, node)),
+            "annotate_expression" => Term::Annotate(AnnotateExpr::new(source, node)),
+            "spec_block" => Term::Spec(SpecBlock::new(source, node)),
             kind if kind.ends_with("literal") => Term::Literal(Literal::new(source, node)),
             _ => unreachable!("{}", node.kind()),
         }
@@ -626,6 +732,48 @@ impl<'a> PackExpr<'a> {
     define_proxy_array_named_field!(body, FieldAssignment);
 }
 
+define_ast_node!(DotExpr, [exp, field]);
+
+impl<'a> DotExpr<'a> {
+    pub fn exp(&self) -> Option<Term> {
+        self.node
+            .child_by_field_name("e")
+            .map(|node| Term::new(self.source, node))
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        self.node
+            .child_by_field_name("f")
+            .map(|node| node.utf8_text(self.source.as_bytes()).unwrap())
+    }
+}
+
+define_ast_node!(IndexExpr, [exp, idx]);
+
+impl<'a> IndexExpr<'a> {
+    pub fn exp(&self) -> Option<Term> {
+        self.node
+            .child_by_field_name("e")
+            .map(|node| Term::new(self.source, node))
+    }
+
+    define_named_field!(idx, Expr);
+}
+
+define_ast_node!(CastExpr, [exp, ty]);
+
+impl<'a> CastExpr<'a> {
+    define_named_field!(exp, Expr);
+    define_named_field!(ty, Type);
+}
+
+define_ast_node!(AnnotateExpr, [exp, ty]);
+
+impl<'a> AnnotateExpr<'a> {
+    define_named_field!(exp, Expr);
+    define_named_field!(ty, Type);
+}
+
 define_ast_node!(FieldAssignment, [field, exp]);
 
 impl<'a> FieldAssignment<'a> {
@@ -650,7 +798,7 @@ impl<'a> Literal<'a> {
             "address_literal" => Literal::Address(val),
             "num_literal" => Literal::Num(val),
             "bool_literal" => Literal::Bool(val),
-            "bytestring_literal" => Literal::ByteString(val),
+            "byte_string_literal" => Literal::ByteString(val),
             _ => unreachable!(),
         }
     }
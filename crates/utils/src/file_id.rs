@@ -0,0 +1,96 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+/// Path of the file, interned into the process-wide table.
+/// Cheap to copy, compare and hash, ordered by the path.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FileId(u32);
+
+#[derive(Default)]
+struct FilePaths {
+    ids: HashMap<&'static str, FileId>,
+    paths: Vec<&'static str>,
+}
+
+lazy_static! {
+    static ref FILE_PATHS: RwLock<FilePaths> = RwLock::new(FilePaths::default());
+}
+
+impl FileId {
+    /// Id of the path, the same path always gets the same id.
+    pub fn new<P: AsRef<Path>>(path: P) -> FileId {
+        let path = path.as_ref().to_str().unwrap();
+        if let Some(id) = FILE_PATHS.read().unwrap().ids.get(path) {
+            return *id;
+        }
+        let mut file_paths = FILE_PATHS.write().unwrap();
+        if let Some(id) = file_paths.ids.get(path) {
+            return *id;
+        }
+        // allocated once per distinct path, `move_lang` requires file names to be 'static
+        let path: &'static str = Box::leak(path.to_owned().into_boxed_str());
+        let id = FileId(file_paths.paths.len() as u32);
+        file_paths.paths.push(path);
+        file_paths.ids.insert(path, id);
+        id
+    }
+
+    pub fn path(self) -> &'static str {
+        FILE_PATHS.read().unwrap().paths[self.0 as usize]
+    }
+}
+
+impl AsRef<Path> for FileId {
+    fn as_ref(&self) -> &Path {
+        Path::new(self.path())
+    }
+}
+
+impl PartialOrd for FileId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FileId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path().cmp(other.path())
+    }
+}
+
+impl fmt::Debug for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.path(), f)
+    }
+}
+
+impl fmt::Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_path_is_interned_once() {
+        let id = FileId::new("/modules/record.move");
+        assert_eq!(FileId::new("/modules/record.move"), id);
+        assert_ne!(FileId::new("/modules/other.move"), id);
+        assert_eq!(id.path(), "/modules/record.move");
+    }
+
+    #[test]
+    fn ids_are_ordered_by_path() {
+        let b = FileId::new("/b.move");
+        let a = FileId::new("/a.move");
+        assert!(a < b);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{leaked_fpath, MoveFile, MoveFilePath};
+use crate::{FileId, MoveFile};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
@@ -30,7 +30,7 @@ pub fn iter_over_move_files<P: AsRef<Path>>(folder: P) -> Vec<String> {
         .collect()
 }
 
-pub fn read_move_files<P: AsRef<Path>>(modules_folder: P) -> Vec<(MoveFilePath, String)> {
+pub fn read_move_files<P: AsRef<Path>>(modules_folder: P) -> Vec<MoveFile> {
     let module_filenames = iter_over_move_files(modules_folder)
         .into_iter()
         .collect::<Vec<_>>();
@@ -50,7 +50,7 @@ pub fn read_move_files<P: AsRef<Path>>(modules_folder: P) -> Vec<(MoveFilePath,
 }
 
 pub fn load_move_file<P: AsRef<Path>>(path: P) -> io::Result<MoveFile> {
-    let fpath = leaked_fpath(path);
+    let fpath = FileId::new(path);
     let mut text = fs::read_to_string(fpath)?;
     ra_vfs::normalize_newlines(&mut text);
     Ok((fpath, text))